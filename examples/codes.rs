@@ -0,0 +1,38 @@
+frack::codes! {
+    /// The error codes for `eggtool`.
+    pub static CODES = "eggtool";
+
+    E0001 => "\
+# Rotten eggs
+
+An egg was used after it had gone bad.
+
+Erroneous code example:
+
+```
+rotten(eggs);
+```
+
+Clean the eggs first with `clean`, or toss them out with **drop**:
+
+* `clean(eggs);`
+* `drop(eggs);`
+",
+}
+
+fn main() {
+    let error = frack::error! {
+        "E0001", "this code smells bad";
+        "src/main.rs", 12, 3;
+        "  rotten(eggs);";
+        2..=13;
+        help "clean the eggs" => ["  clean(eggs);"; 2..=12];
+    };
+
+    // ends with a footer pointing at `eggtool --explain E0001`
+    println!("{}", CODES.explained(&error));
+
+    if let Some(entry) = CODES.entry("E0001") {
+        println!("{}", entry.markdown());
+    }
+}
@@ -1,4 +1,4 @@
-use frack::{Code, Error, File, Help, Marker, Note};
+use frack::{Applicability, Child, Code, Error, File, Help, Marker, SuggestionStyle};
 
 fn main() {
     let code = Code::single(
@@ -13,17 +13,7 @@ fn main() {
         }),
     );
 
-    let fix = Code::single(
-        "    let Foo { x, y } = z;".to_string(),
-        8,
-        Some(Marker {
-            range: 15..=17,
-            symbol: '~',
-            color: 10,
-            message: None,
-            color_span: true,
-        }),
-    );
+    let fix = Code::suggestion("    let Foo { x } = z;", "    let Foo { x, y } = z;", 8);
 
     let error = Error {
         error_code: "AMOGUS".to_string(),
@@ -35,17 +25,17 @@ fn main() {
         },
 
         code,
-        helps: vec![
+        children: vec![
             Help {
                 message: "`y` lives matter".to_string(),
                 suggestion: Some(fix),
-            },
-            Help {
-                message: "don't discriminate next time".to_string(),
-                suggestion: None,
-            },
+                style: SuggestionStyle::Verbose,
+                applicability: Applicability::Unspecified,
+            }
+            .into(),
+            Child::help("don't discriminate next time")
+                .child(Child::note("error generated by Kyllingene/frack")),
         ],
-        notes: vec![Note("error generated by Kyllingene/frack".to_string())],
     };
 
     println!("{error}");
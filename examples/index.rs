@@ -0,0 +1,47 @@
+use frack::index::Index;
+
+frack::codes! {
+    static CODES = "eggtool";
+
+    E0001 => "\
+# Rotten eggs
+
+An egg was used after it had gone bad.
+
+```
+rotten(eggs);
+```
+
+If the eggs are fine but dirty, see `E0002`.
+",
+    E0002 => "\
+# Dirty eggs
+
+An egg was used without being _cleaned_ first.
+",
+}
+
+fn main() {
+    let rotten = frack::error! {
+        "E0001", "this code smells bad";
+        "src/main.rs", 12, 3;
+        "  rotten(eggs);";
+        2..=13;
+        help "toss them out" => ["  drop(eggs);"; 2..=5];
+    };
+
+    let dirty = frack::error! {
+        "E0002", "these eggs are filthy";
+        "src/main.rs", 4, 3;
+        "  cook(eggs);";
+        7..=10 => "not cleaned";
+        note "ferris has feelings too";
+    };
+
+    let mut index = Index::new(&CODES);
+    index.example("E0001", &rotten);
+    index.example("E0002", &dirty);
+    index.write("target/error-index").unwrap();
+
+    println!("wrote error index to target/error-index/index.html");
+}
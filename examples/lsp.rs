@@ -0,0 +1,81 @@
+use std::io::Cursor;
+
+use frack::json::{self, Value};
+use frack::lsp::{self, Server};
+
+fn main() {
+    let error = frack::error! {
+        "E0308", "mismatched types";
+        "src/main.rs", 2, 18;
+        "    let x: i32 = \"hello\";";
+        17..=23 => "expected `i32`, found `&str`";
+        help "try using a number" => ["    let x: i32 = 5;"; 17..=17];
+    };
+
+    let path = std::env::temp_dir().join("frack-lsp-example.json");
+    let diagnostic = json::to_json(&error.into());
+    std::fs::write(&path, format!("{diagnostic}\n")).unwrap();
+
+    let request = |id: usize, method: &str, params: Value| {
+        Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id.into()),
+            ("method", method.into()),
+            ("params", params),
+        ])
+    };
+    let notification = |method: &str| {
+        Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", Value::object([])),
+        ])
+    };
+
+    let code_action = Value::object([
+        (
+            "textDocument",
+            Value::object([("uri", "file:///project/src/main.rs".into())]),
+        ),
+        (
+            "range",
+            Value::object([
+                (
+                    "start",
+                    Value::object([("line", 1.into()), ("character", 0.into())]),
+                ),
+                (
+                    "end",
+                    Value::object([("line", 1.into()), ("character", 0.into())]),
+                ),
+            ]),
+        ),
+    ]);
+
+    let mut script = Vec::new();
+    for message in [
+        request(
+            1,
+            "initialize",
+            Value::object([("rootUri", "file:///project".into())]),
+        ),
+        notification("initialized"),
+        request(2, "textDocument/codeAction", code_action),
+        request(3, "shutdown", Value::Null),
+        notification("exit"),
+    ] {
+        lsp::write_message(&mut script, &message).unwrap();
+    }
+
+    let mut output = Vec::new();
+    Server::new(&path)
+        .run(Cursor::new(script), &mut output)
+        .unwrap();
+
+    let mut output = Cursor::new(output);
+    while let Some(message) = lsp::read_message(&mut output).unwrap() {
+        println!("{message}");
+    }
+
+    let _ = std::fs::remove_file(path);
+}
@@ -1,4 +1,4 @@
-use frack::{Code, Error, File, Help, Line, Marker};
+use frack::{Applicability, Code, Error, File, Help, Line, Marker, SuggestionStyle};
 
 fn main() {
     let code = Code(vec![
@@ -36,7 +36,7 @@ fn main() {
         },
 
         code,
-        helps: vec![Help {
+        children: vec![Help {
             message: "consider using the available `ToString` impl".to_string(),
             suggestion: Some(Code::single(
                 "    12_i32.to_string()",
@@ -49,8 +49,10 @@ fn main() {
                     color_span: true,
                 }),
             )),
-        }],
-        notes: Vec::new(),
+            style: SuggestionStyle::Verbose,
+            applicability: Applicability::Unspecified,
+        }
+        .into()],
     };
 
     println!("{error}");
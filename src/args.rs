@@ -0,0 +1,258 @@
+//! `rustc`-style errors for command-line arguments.
+//!
+//! [`Argv`] treats the command line as a line of source code, so errors can
+//! point at the argument they're about:
+//!
+//! ```text
+//! error[INVALID]: invalid format
+//!   |
+//! 1 | mytool --format jsn
+//!   |                 ^^^ unrecognized format
+//!   |
+//! help: a format with a similar name exists: `json`
+//! ```
+//!
+//! ```rust,no_run
+//! use frack::args::Argv;
+//!
+//! let argv = Argv::from_env();
+//! let fail = |err: frack::Error| -> ! {
+//!     eprintln!("{err}");
+//!     std::process::exit(1);
+//! };
+//!
+//! match argv.get(1) {
+//!     Some("build") => { /* ... */ }
+//!     Some("run") => { /* ... */ }
+//!     Some("biuld") => {
+//!         let mut err = argv.invalid(1, "command", "unrecognized command");
+//!         let help = argv.suggest("a command with a similar name exists", 1, "build");
+//!         err.children.push(help.into());
+//!         fail(err)
+//!     }
+//!     Some(_) => fail(argv.invalid(1, "command", "unrecognized command")),
+//!     None => fail(argv.missing("command")),
+//! }
+//!
+//! if let Some(i) = (2..argv.len()).next() {
+//!     fail(argv.unexpected(i));
+//! }
+//! ```
+
+use std::ops::{Range, RangeInclusive};
+
+use crate::{edit, Applicability, Child, Code, Error, File, Help, Marker, SuggestionStyle};
+
+/// The arguments of a program, laid out as a line of source code.
+///
+/// Arguments are joined with spaces, quoted the way a shell would need them,
+/// so the line can be copied back into a shell. Index 0 is the program.
+pub struct Argv {
+    args: Vec<String>,
+    line: String,
+    spans: Vec<Range<usize>>,
+}
+
+impl Argv {
+    /// The arguments of this process. The program is named by the file name
+    /// of the first argument, e.g. `frack` rather than `./target/debug/frack`.
+    pub fn from_env() -> Self {
+        let mut args = std::env::args();
+        let program = args.next().unwrap_or_default();
+        let program = std::path::Path::new(&program)
+            .file_stem()
+            .map_or_else(|| program.clone(), |p| p.to_string_lossy().into_owned());
+
+        Self::new(program, args)
+    }
+
+    /// The arguments `args` of `program`.
+    pub fn new(program: impl ToString, args: impl IntoIterator<Item = impl ToString>) -> Self {
+        let args: Vec<String> = std::iter::once(program.to_string())
+            .chain(args.into_iter().map(|a| a.to_string()))
+            .collect();
+
+        let mut line = String::new();
+        let mut spans = Vec::new();
+        for arg in &args {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            let start = line.len();
+            line.push_str(&quote(arg));
+            spans.push(start..line.len());
+        }
+
+        Self { args, line, spans }
+    }
+
+    /// All of the arguments, starting with the program.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Argument `i`, if there is one.
+    pub fn get(&self, i: usize) -> Option<&str> {
+        self.args.get(i).map(String::as_str)
+    }
+
+    /// The number of arguments, including the program.
+    pub fn len(&self) -> usize {
+        self.args.len()
+    }
+
+    /// Whether there are no arguments at all, not even the program.
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+
+    /// The command line.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// The byte range of argument `i` in the [line](Self::line), including any
+    /// quotes.
+    pub fn span(&self, i: usize) -> Option<Range<usize>> {
+        self.spans.get(i).cloned()
+    }
+
+    /// The columns to mark for argument `i`, or the space after the last
+    /// argument if there's no such argument.
+    fn columns(&self, i: usize) -> RangeInclusive<usize> {
+        let column = |byte| edit::column(&self.line, byte);
+        match self.spans.get(i) {
+            Some(span) => column(span.start)..=column(span.end) - 1,
+            None => {
+                let end = column(self.line.len()) + 1;
+                end..=end + 3
+            }
+        }
+    }
+
+    /// An error pointing at argument `i`, or after the last argument if
+    /// there's no such argument.
+    pub fn error(
+        &self,
+        error_code: impl ToString,
+        message: impl ToString,
+        i: usize,
+        label: impl ToString,
+    ) -> Error {
+        let range = self.columns(i);
+        Error {
+            error_code: error_code.to_string(),
+            message: message.to_string(),
+            file: File {
+                path: String::new(),
+                line: 1,
+                col: range.start() + 1,
+            },
+            code: Code::single(
+                &self.line,
+                1,
+                Some(Marker {
+                    range,
+                    symbol: '^',
+                    color: 9,
+                    message: Some(label.to_string()),
+                    color_span: false,
+                }),
+            ),
+            children: Vec::new(),
+        }
+    }
+
+    /// An error for a required argument that wasn't given, e.g.
+    /// `error[MISSING]: must provide file`.
+    pub fn missing(&self, what: &str) -> Error {
+        self.error(
+            "MISSING",
+            format!("must provide {what}"),
+            self.len(),
+            format!("no {what} provided"),
+        )
+    }
+
+    /// An error for argument `i` having an invalid value, e.g.
+    /// `error[INVALID]: invalid format`.
+    pub fn invalid(&self, i: usize, what: &str, label: impl ToString) -> Error {
+        self.error("INVALID", format!("invalid {what}"), i, label)
+    }
+
+    /// An error for argument `i` not being expected at all.
+    pub fn unexpected(&self, i: usize) -> Error {
+        self.error("INVALID", "unexpected argument", i, "unexpected argument")
+    }
+
+    /// An error for arguments `i` and `j` not being allowed together. Points at
+    /// the later of the two.
+    pub fn conflicting(&self, i: usize, j: usize) -> Error {
+        let (first, second) = (i.min(j), i.max(j));
+        let name = |i| self.get(i).map_or_else(String::new, quote);
+
+        let mut err = self.error(
+            "INVALID",
+            "conflicting arguments",
+            second,
+            format!("conflicts with `{}`", name(first)),
+        );
+        err.children.push(Child::help(format!(
+            "`{}` and `{}` can't be used together; remove one of them",
+            name(first),
+            name(second),
+        )));
+        err
+    }
+
+    /// A help suggesting `replacement` for argument `i`. The suggestion is the
+    /// command line with it replaced, shown
+    /// [inline](crate::SuggestionStyle::Inline) as just `replacement`.
+    pub fn suggest(&self, message: impl ToString, i: usize, replacement: &str) -> Help {
+        let Some(span) = self.spans.get(i) else {
+            return Help {
+                message: message.to_string(),
+                suggestion: None,
+                style: SuggestionStyle::Inline,
+                applicability: Applicability::Unspecified,
+            };
+        };
+
+        let replacement = quote(replacement);
+        let line = format!(
+            "{}{replacement}{}",
+            &self.line[..span.start],
+            &self.line[span.end..]
+        );
+        let start = edit::column(&self.line, span.start);
+        let end = start + replacement.chars().count().max(1) - 1;
+
+        Help {
+            message: message.to_string(),
+            suggestion: Some(Code::single(
+                &line,
+                1,
+                Some(Marker {
+                    range: start..=end,
+                    symbol: '~',
+                    color: 10,
+                    message: None,
+                    color_span: true,
+                }),
+            )),
+            style: SuggestionStyle::Inline,
+            applicability: Applicability::MaybeIncorrect,
+        }
+    }
+}
+
+/// Quote an argument the way a shell would need it.
+fn quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
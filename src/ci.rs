@@ -0,0 +1,164 @@
+//! Annotations for CI systems, so diagnostics show up inline on pull and merge
+//! requests.
+//!
+//! ```rust,no_run
+//! let error = frack::error! {
+//!     "E0308", "mismatched types";
+//!     "src/main.rs", 2, 18;
+//!     "    let x: i32 = \"hello\";";
+//!     17..=23 => "expected `i32`, found `&str`";
+//! };
+//!
+//! let diag = error.into();
+//! print!("{}", frack::ci::GitHub(&diag));
+//! println!("{}", frack::ci::gitlab(&[diag]));
+//! ```
+
+use std::fmt;
+
+use crate::json::Value;
+use crate::{edit, Diagnostic, Level};
+
+/// A GitHub Actions workflow command, e.g.
+/// `::error file=src/main.rs,line=2,col=18,endColumn=24,title=E0308::mismatched types`.
+///
+/// The message includes the primary marker's message, if any.
+pub struct GitHub<'a>(pub &'a Diagnostic);
+
+impl fmt::Display for GitHub<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diag = self.0;
+        let file = diag.file();
+
+        write!(f, "::{}", diag.level())?;
+
+        let mut properties = Vec::new();
+        if !file.path.is_empty() {
+            properties.push(("file", file.path.clone()));
+
+            match location(diag) {
+                Some(loc) => {
+                    properties.push(("line", loc.line.to_string()));
+                    properties.push(("col", loc.col.to_string()));
+                    properties.push(("endColumn", loc.end_col.to_string()));
+                }
+
+                None => {
+                    properties.push(("line", file.line.to_string()));
+                    properties.push(("col", file.col.to_string()));
+                }
+            }
+        }
+
+        let title = diag
+            .error_code()
+            .map_or_else(|| diag.level().to_string(), str::to_string);
+        properties.push(("title", title));
+
+        for (i, (key, value)) in properties.iter().enumerate() {
+            let sep = if i == 0 { ' ' } else { ',' };
+            write!(f, "{sep}{key}={}", escape_property(value))?;
+        }
+
+        writeln!(f, "::{}", escape_data(&diag.labeled_message()))
+    }
+}
+
+/// Serialize diagnostics as a GitLab Code Quality report.
+pub fn gitlab(diagnostics: &[Diagnostic]) -> Value {
+    let issues = diagnostics
+        .iter()
+        .map(|diag| {
+            let file = diag.file();
+            let check = diag
+                .error_code()
+                .map_or_else(|| diag.level().to_string(), str::to_string);
+            let severity = match diag.level() {
+                Level::Error => "major",
+                Level::Warning => "minor",
+            };
+
+            let (begin, end) = match location(diag) {
+                Some(loc) => (position(loc.line, loc.col), position(loc.line, loc.end_col)),
+                None => (position(file.line, file.col), position(file.line, file.col)),
+            };
+
+            let path = file.path.replace('\\', "/");
+            let path = path.trim_start_matches("./");
+            let fingerprint = fingerprint(&[
+                &check,
+                diag.message(),
+                path,
+                &file.line.to_string(),
+                &file.col.to_string(),
+            ]);
+
+            Value::object([
+                ("type", "issue".into()),
+                ("check_name", check.into()),
+                ("description", diag.labeled_message().into()),
+                ("categories", Value::Array(vec!["Bug Risk".into()])),
+                ("severity", severity.into()),
+                ("fingerprint", fingerprint.into()),
+                (
+                    "location",
+                    Value::object([
+                        ("path", path.into()),
+                        ("positions", Value::object([("begin", begin), ("end", end)])),
+                    ]),
+                ),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Value::Array(issues)
+}
+
+/// Where the primary marker of a diagnostic is, with 1-based, inclusive
+/// columns.
+struct Location {
+    line: usize,
+    col: usize,
+    end_col: usize,
+}
+
+fn location(diag: &Diagnostic) -> Option<Location> {
+    let line = diag.primary()?;
+    let range = edit::marked(line, &line.marker.as_ref()?.range);
+    let column = |byte| edit::column(&line.code, byte);
+
+    let col = column(range.start) + 1;
+    Some(Location {
+        line: line.line_number,
+        col,
+        end_col: column(range.end).max(col),
+    })
+}
+
+fn position(line: usize, column: usize) -> Value {
+    Value::object([("line", line.into()), ("column", column.into())])
+}
+
+/// A stable hash of the given parts, as hex (FNV-1a).
+fn fingerprint(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for b in part.bytes().chain([0]) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{hash:016x}")
+}
+
+/// Escape the message of a workflow command.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a workflow command.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
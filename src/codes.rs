@@ -0,0 +1,234 @@
+//! A registry of error codes and their long-form explanations.
+//!
+//! Declare one with the [`codes!`](crate::codes!) macro; see it for details.
+//! Display an error [explained](Registry::explained) by it to end it with a
+//! footer pointing at `--explain`.
+
+use std::fmt;
+
+use crate::args::Argv;
+use crate::markdown::Markdown;
+use crate::{bold, normal, similar, Error};
+
+/// A set of error codes, each with a Markdown explanation.
+///
+/// Create one using [`codes!`](crate::codes!), which validates the codes at
+/// compile time.
+pub struct Registry {
+    /// The name of the tool, as in `<tool> --explain E0001`.
+    pub tool: &'static str,
+
+    /// Every registered code, in declaration order.
+    pub entries: &'static [Entry],
+}
+
+/// A single error code in a [`Registry`].
+pub struct Entry {
+    /// The code itself, e.g. `E0001`.
+    pub code: &'static str,
+
+    /// The long-form explanation, in Markdown.
+    pub explanation: &'static str,
+}
+
+impl Registry {
+    /// `error`, displayed with the `For more information about this error,
+    /// ...` footer after it if its code is in this registry.
+    pub fn explained<'a>(&'a self, error: &'a Error) -> Explained<'a> {
+        Explained {
+            registry: self,
+            error,
+        }
+    }
+
+    /// Look up the entry for an error code.
+    pub fn entry(&self, code: &str) -> Option<&'static Entry> {
+        self.entries.iter().find(|e| e.code == code)
+    }
+
+    /// Look up the explanation for an error code.
+    ///
+    /// To print it for the terminal, wrap it in a [`Markdown`].
+    pub fn explain(&self, code: &str) -> Option<&'static str> {
+        self.entry(code).map(|e| e.explanation)
+    }
+
+    /// An error for argument `i` not being a code in this registry, for
+    /// handling `<tool> --explain <code>`. Suggests a code with a similar
+    /// name, if there is one.
+    pub fn invalid(&self, argv: &Argv, i: usize) -> Error {
+        let mut err = argv.invalid(i, "error code", "no explanation for this code");
+        let codes = self.entries.iter().map(|e| e.code);
+        if let Some(s) = argv.get(i).and_then(|code| similar::best(code, codes)) {
+            let help = argv.suggest("a code with a similar name exists", i, s);
+            err.children.push(help.into());
+        }
+
+        err
+    }
+
+    /// Create the `For more information about this error, ...` footer for
+    /// the given codes.
+    ///
+    /// Codes that aren't registered are skipped; if none are, the footer
+    /// displays as nothing. [`explained`](Self::explained) adds it to a single
+    /// error; this is for printing one footer after many errors instead.
+    pub fn footer<'a>(&self, codes: impl IntoIterator<Item = &'a str>) -> Footer {
+        let mut found: Vec<_> = codes
+            .into_iter()
+            .filter_map(|c| self.entry(c))
+            .map(|e| e.code)
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+
+        Footer {
+            tool: self.tool,
+            codes: found,
+        }
+    }
+}
+
+/// The footer pointing users at `--explain`, in `rustc` style.
+///
+/// Create one using [`Registry::footer`]. To display using ANSI escape codes,
+/// use the [`Display`](fmt::Display) impl.
+pub struct Footer {
+    /// The name of the tool, as in `<tool> --explain E0001`.
+    pub tool: &'static str,
+
+    /// The codes with explanations, sorted.
+    pub codes: Vec<&'static str>,
+}
+
+impl fmt::Display for Footer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(first) = self.codes.first() else {
+            return Ok(());
+        };
+
+        bold(f)?;
+        if self.codes.len() == 1 {
+            write!(f, "For more information about this error")?;
+        } else {
+            writeln!(
+                f,
+                "Some errors have detailed explanations: {}.",
+                self.codes.join(", ")
+            )?;
+            write!(f, "For more information about an error")?;
+        }
+
+        write!(f, ", try `{} --explain {first}`.", self.tool)?;
+        normal(f)?;
+        writeln!(f)
+    }
+}
+
+/// An [`Error`] followed by the footer for its code.
+///
+/// Create one using [`Registry::explained`]. To display using ANSI escape
+/// codes, use the [`Display`](fmt::Display) impl.
+pub struct Explained<'a> {
+    registry: &'a Registry,
+    error: &'a Error,
+}
+
+impl fmt::Display for Explained<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+
+        let footer = self.registry.footer([self.error.error_code.as_str()]);
+        if !footer.codes.is_empty() {
+            writeln!(f)?;
+            write!(f, "{footer}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// The explanation, rendered for the terminal.
+    pub fn markdown(&self) -> Markdown<'static> {
+        Markdown(self.explanation)
+    }
+}
+
+/// Check that every code is an uppercase ASCII letter followed by four
+/// digits, and that no code appears twice.
+///
+/// Used by [`codes!`](crate::codes!) to fail compilation on bad codes.
+#[doc(hidden)]
+pub const fn validate(codes: &[&str]) {
+    let mut i = 0;
+    while i < codes.len() {
+        let code = codes[i].as_bytes();
+        if code.len() != 5 || !code[0].is_ascii_uppercase() {
+            panic!("error codes must be an uppercase letter followed by four digits, e.g. `E0001`");
+        }
+
+        let mut j = 1;
+        while j < 5 {
+            if !code[j].is_ascii_digit() {
+                panic!(
+                    "error codes must be an uppercase letter followed by four digits, e.g. `E0001`"
+                );
+            }
+            j += 1;
+        }
+
+        let mut j = 0;
+        while j < i {
+            let other = codes[j].as_bytes();
+            let mut k = 0;
+            while k < 5 && other[k] == code[k] {
+                k += 1;
+            }
+
+            if k == 5 {
+                panic!("duplicate error code");
+            }
+            j += 1;
+        }
+
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Plain;
+
+    crate::codes! {
+        static CODES = "mytool";
+
+        E0001 => "An explanation.",
+    }
+
+    fn error(code: &str) -> crate::Error {
+        crate::error! {
+            code, "oops";
+            "src/main.rs", 1, 1;
+            "let x = 1;";
+            0..=2;
+        }
+    }
+
+    #[test]
+    fn only_explained_errors_have_footers() {
+        let footer = "For more information about this error, try `mytool --explain E0001`.\n";
+        let known = error("E0001");
+        assert!(!Plain(&known).to_string().contains("--explain"));
+        assert_eq!(
+            Plain(CODES.explained(&known)).to_string(),
+            format!("{}\n{footer}", Plain(&known))
+        );
+
+        let unknown = error("E0002");
+        assert_eq!(
+            Plain(CODES.explained(&unknown)).to_string(),
+            Plain(&unknown).to_string()
+        );
+    }
+}
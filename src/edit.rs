@@ -0,0 +1,304 @@
+//! Finding what changed between a line of code and its suggested revision.
+
+use std::borrow::Cow;
+use std::ops::{Range, RangeInclusive};
+
+use crate::{Code, Line};
+
+/// A replacement of part of a line.
+pub(crate) struct Edit {
+    /// The byte range of the original line to replace.
+    pub range: Range<usize>,
+
+    /// The text to replace it with.
+    pub replacement: String,
+}
+
+/// The smallest single edit that turns `old` into `new`, or `None` if they're
+/// identical.
+pub(crate) fn between(old: &str, new: &str) -> Option<Edit> {
+    if old == new {
+        return None;
+    }
+
+    let prefix: usize = old
+        .chars()
+        .zip(new.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let suffix: usize = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    Some(Edit {
+        range: prefix..old.len() - suffix,
+        replacement: new[prefix..new.len() - suffix].to_string(),
+    })
+}
+
+/// The parts of `old` and `new` that differ, as pairs of character ranges in
+/// each, found by a character-level diff. Each separate edit is its own
+/// change, e.g. `let x = 1;` to `let mut x = 1u8;` is two insertions.
+pub(crate) fn changes(old: &str, new: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+
+    // the diff takes quadratic space, so give up on precision for huge lines
+    if old.len().saturating_mul(new.len()) > 1 << 22 {
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        return match old.len() - prefix - suffix + new.len() - prefix - suffix {
+            0 => Vec::new(),
+            _ => vec![(prefix..old.len() - suffix, prefix..new.len() - suffix)],
+        };
+    }
+
+    // matching from the start and from the end place edits differently, so
+    // use whichever splits them up less
+    let forward = diff(&old, &new);
+    let backward: Vec<_> = {
+        let rev = |s: &[char]| s.iter().rev().copied().collect::<Vec<_>>();
+        let flip = |r: Range<usize>, len: usize| len - r.end..len - r.start;
+        diff(&rev(&old), &rev(&new))
+            .into_iter()
+            .rev()
+            .map(|(a, b)| (flip(a, old.len()), flip(b, new.len())))
+            .collect()
+    };
+    let mut changes = if backward.len() <= forward.len() {
+        backward
+    } else {
+        forward
+    };
+
+    slide(&mut changes, &old, &new);
+    changes
+}
+
+/// Slide pure insertions and removals to the best word boundaries they can
+/// move to, e.g. removing `mut ` rather than `t mu` from `let mut x`.
+fn slide(changes: &mut [(Range<usize>, Range<usize>)], old: &[char], new: &[char]) {
+    // how good a place to split `text` before `k` is
+    let boundary = |text: &[char], k: usize| match (k.checked_sub(1).map(|k| text[k]), text.get(k))
+    {
+        (None, _) | (_, None) => 3,
+        (Some(x), Some(y)) if x.is_whitespace() || y.is_whitespace() => 2,
+        (Some(x), Some(y)) if !(x.is_alphanumeric() && y.is_alphanumeric()) => 1,
+        _ => 0,
+    };
+
+    for i in 0..changes.len() {
+        let (a, b) = &changes[i];
+        let (text, range, min, max) = match (a.is_empty(), b.is_empty()) {
+            (true, false) => (
+                new,
+                b.clone(),
+                i.checked_sub(1).map_or(0, |p| changes[p].1.end),
+                changes.get(i + 1).map_or(new.len(), |n| n.1.start),
+            ),
+            (false, true) => (
+                old,
+                a.clone(),
+                i.checked_sub(1).map_or(0, |p| changes[p].0.end),
+                changes.get(i + 1).map_or(old.len(), |n| n.0.start),
+            ),
+            _ => continue,
+        };
+
+        let (mut left, mut right) = (0, 0);
+        while range.start - left > min && text[range.start - left - 1] == text[range.end - left - 1]
+        {
+            left += 1;
+        }
+        while range.end + right < max && text[range.start + right] == text[range.end + right] {
+            right += 1;
+        }
+
+        let score = |shift: isize| {
+            let (s, e) = (range.start as isize + shift, range.end as isize + shift);
+            boundary(text, s as usize) + boundary(text, e as usize)
+        };
+        let Some(shift) = (-(left as isize)..=right as isize).max_by_key(|&d| score(d)) else {
+            continue;
+        };
+
+        let (a, b) = &mut changes[i];
+        for r in [a, b] {
+            r.start = (r.start as isize + shift) as usize;
+            r.end = (r.end as isize + shift) as usize;
+        }
+    }
+}
+
+/// The runs of differing characters between `old` and `new`, matching
+/// characters as early as possible.
+fn diff(old: &[char], new: &[char]) -> Vec<(Range<usize>, Range<usize>)> {
+    // longest common subsequence lengths of the suffixes of `old` and `new`
+    let width = new.len() + 1;
+    let mut lcs = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old[i] == new[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let (from_i, from_j) = (i, j);
+        if j == new.len() || (i < old.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1]) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+
+        match changes.last_mut() {
+            Some((a, b)) if a.end == from_i && b.end == from_j => {
+                a.end = i;
+                b.end = j;
+            }
+            _ => changes.push((from_i..i, from_j..j)),
+        }
+    }
+
+    changes
+}
+
+/// The byte offset of the character at `column` in `code`, or the length of
+/// `code` if it's past the end.
+pub(crate) fn byte(code: &str, column: usize) -> usize {
+    code.char_indices()
+        .nth(column)
+        .map_or(code.len(), |(b, _)| b)
+}
+
+/// The byte range in `code` that a range of character columns covers.
+pub(crate) fn bytes(code: &str, columns: &RangeInclusive<usize>) -> Range<usize> {
+    byte(code, *columns.start())..byte(code, columns.end() + 1)
+}
+
+/// The character column, starting at 0, of the byte offset `byte` in `code`.
+pub(crate) fn column(code: &str, byte: usize) -> usize {
+    code.get(..byte).map_or(byte, |s| s.chars().count())
+}
+
+/// The byte range in a line that a marker's range, in characters, covers.
+pub(crate) fn marked(line: &Line, range: &RangeInclusive<usize>) -> Range<usize> {
+    bytes(&line.code, range)
+}
+
+/// A suggested line as it's meant: with the marked region removed if it's
+/// marked with `-`, or as-is otherwise.
+pub(crate) fn revised(line: &Line) -> Cow<'_, str> {
+    match &line.marker {
+        Some(m) if m.symbol == '-' => {
+            let range = marked(line, &m.range);
+            Cow::Owned(format!(
+                "{}{}",
+                &line.code[..range.start],
+                &line.code[range.end..]
+            ))
+        }
+        _ => Cow::Borrowed(&line.code),
+    }
+}
+
+/// The edits a suggestion makes to `code`, each paired with the line it edits.
+///
+/// A suggested line marked with `-` removes its marked region. A suggested
+/// line with no counterpart in `code` can only be resolved if it's marked
+/// with a single `+`, since the inserted text is all that differs from the
+/// original; other such lines are left out, since what they replace is
+/// unknown. Duplicate edits are left out, so a line split up to show several
+/// markers is only edited once.
+pub(crate) fn suggested<'a>(code: &'a Code, suggestion: &'a Code) -> Vec<(&'a Line, Edit)> {
+    let mut edits: Vec<(&Line, Edit)> = Vec::new();
+    for new in suggestion.iter() {
+        let old = code.iter().find(|l| l.line_number == new.line_number);
+        let edit = match (old, &new.marker) {
+            (_, Some(m)) if m.symbol == '-' => {
+                let line = old.filter(|o| o.code == new.code).unwrap_or(new);
+                let range = marked(new, &m.range);
+                Some((
+                    line,
+                    Edit {
+                        range,
+                        replacement: String::new(),
+                    },
+                ))
+            }
+            (Some(old), _) => between(&old.code, &new.code).map(|e| (old, e)),
+            (None, Some(m)) if m.symbol == '+' => {
+                let markers = suggestion
+                    .iter()
+                    .filter(|l| l.line_number == new.line_number)
+                    .count();
+                let range = marked(new, &m.range);
+                (markers == 1).then(|| {
+                    let edit = Edit {
+                        range: range.start..range.start,
+                        replacement: new.code[range].to_string(),
+                    };
+                    (new, edit)
+                })
+            }
+            (None, _) => None,
+        };
+
+        let Some((line, edit)) = edit else {
+            continue;
+        };
+        let duplicate = edits.iter().any(|(l, e)| {
+            l.line_number == line.line_number
+                && e.range == edit.range
+                && e.replacement == edit.replacement
+        });
+        if !duplicate {
+            edits.push((line, edit));
+        }
+    }
+
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::changes;
+
+    #[test]
+    fn separate_edits_are_separate_changes() {
+        assert_eq!(
+            changes("let x = 1;", "let mut x = 1u8;"),
+            [(4..4, 4..8), (9..9, 13..15)],
+        );
+        assert_eq!(changes("foo(a, b, c)", "foo(a, c)"), [(7..10, 7..7)],);
+    }
+
+    #[test]
+    fn changes_are_exact() {
+        assert_eq!(changes("let abc = 1;", "let abd = 1;"), [(6..7, 6..7)]);
+        assert_eq!(changes("same", "same"), []);
+    }
+}
@@ -0,0 +1,322 @@
+//! Applying suggestions to source code, like `rustfix`.
+//!
+//! ```rust,no_run
+//! use frack::{fix, json, Applicability};
+//!
+//! let input = std::io::stdin().lock();
+//! let diagnostics: Vec<_> = json::Stream::new(input)
+//!     .filter_map(Result::ok)
+//!     .map(|m| m.diagnostic)
+//!     .collect();
+//!
+//! let fixes: Vec<_> = fix::fixes(&diagnostics)
+//!     .into_iter()
+//!     .filter(|f| f.path == "src/main.rs")
+//!     .filter(|f| f.applicability == Applicability::MachineApplicable)
+//!     .collect();
+//!
+//! let source = std::fs::read_to_string("src/main.rs").unwrap();
+//! let applied = fix::apply(&source, &fixes);
+//! for (fix, err) in &applied.skipped {
+//!     eprintln!("skipped `{}`: {err}", fix.message);
+//! }
+//!
+//! std::fs::write("src/main.rs", applied.source).unwrap();
+//! ```
+
+use std::fmt;
+
+use crate::{edit, Applicability, Diagnostic};
+
+/// A suggestion from a [`Help`](crate::Help), ready to apply to its file.
+pub struct Fix {
+    /// The file to fix.
+    pub path: String,
+
+    /// The message of the help the suggestion came from.
+    pub message: String,
+
+    pub applicability: Applicability,
+
+    /// The suggested lines.
+    pub changes: Vec<Change>,
+}
+
+/// A suggested line of a [`Fix`].
+pub struct Change {
+    pub line_number: usize,
+
+    /// The line as the diagnostic showed it, if it did.
+    ///
+    /// If the file's line differs, the diagnostic is out of date, so the fix
+    /// isn't applied.
+    pub original: Option<String>,
+
+    /// The suggested line, with any region marked `-` removed.
+    pub code: String,
+}
+
+/// The fixes suggested by the helps of `diagnostics`, in order.
+pub fn fixes(diagnostics: &[Diagnostic]) -> Vec<Fix> {
+    let mut fixes = Vec::new();
+    for diag in diagnostics {
+        for help in diag.helps() {
+            let Some(suggestion) = help.suggestion.as_ref().filter(|s| !s.is_empty()) else {
+                continue;
+            };
+
+            let changes = suggestion
+                .iter()
+                .map(|line| Change {
+                    line_number: line.line_number,
+                    original: diag
+                        .code()
+                        .iter()
+                        .find(|l| l.line_number == line.line_number)
+                        .map(|l| l.code.clone()),
+                    code: edit::revised(line).into_owned(),
+                })
+                .collect();
+
+            fixes.push(Fix {
+                path: diag.file().path.clone(),
+                message: help.message.clone(),
+                applicability: help.applicability,
+                changes,
+            });
+        }
+    }
+
+    fixes
+}
+
+/// Why a [`Fix`] wasn't applied.
+#[derive(Debug)]
+pub enum FixError {
+    /// The source doesn't have a line the fix changes, or it differs from what
+    /// the diagnostic showed.
+    Mismatch { line: usize },
+
+    /// The fix overlaps an earlier fix, or its own changes overlap.
+    Conflict { line: usize },
+}
+
+impl fmt::Display for FixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch { line } => {
+                write!(f, "line {line} doesn't match what the diagnostic showed")
+            }
+            Self::Conflict { line } => write!(f, "overlaps another fix on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// The result of [applying](apply) fixes.
+pub struct Applied<'a> {
+    /// The fixed source.
+    pub source: String,
+
+    /// The fixes that were applied, in order.
+    pub applied: Vec<&'a Fix>,
+
+    /// The fixes that weren't applied, and why.
+    pub skipped: Vec<(&'a Fix, FixError)>,
+}
+
+/// Apply `fixes` to `source`, in order.
+///
+/// A fix that overlaps an earlier one, whose changes overlap each other, or
+/// that doesn't match `source`, is skipped entirely. Edits identical to ones
+/// already made are ignored, so duplicate suggestions don't conflict.
+pub fn apply<'a>(source: &str, fixes: impl IntoIterator<Item = &'a Fix>) -> Applied<'a> {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    let line = |number: usize| {
+        let start = *starts.get(number.checked_sub(1)?)?;
+        let text = source[start..].split('\n').next().unwrap_or("");
+        (start < source.len() || number == 1).then(|| (start, text.trim_end_matches('\r')))
+    };
+
+    // (start, end, replacement), as byte offsets in `source`
+    let mut edits: Vec<(usize, usize, String)> = Vec::new();
+    let mut applied = Vec::new();
+    let mut skipped = Vec::new();
+    'fixes: for fix in fixes {
+        let mut new = Vec::new();
+        for change in &fix.changes {
+            let number = change.line_number;
+            let Some((start, text)) =
+                line(number).filter(|(_, text)| change.original.as_ref().is_none_or(|o| o == text))
+            else {
+                skipped.push((fix, FixError::Mismatch { line: number }));
+                continue 'fixes;
+            };
+
+            let Some(e) = edit::between(text, &change.code) else {
+                continue;
+            };
+            let edit = (start + e.range.start, start + e.range.end, e.replacement);
+
+            // a line split up to show several markers suggests the same edit
+            // once for each of them
+            if edits.iter().chain(&new).any(|e| *e == edit) {
+                continue;
+            }
+
+            let (a, b) = (edit.0, edit.1);
+            let overlaps = |&(c, d, _): &(usize, usize, String)| a < d && c < b || a == c;
+            if edits.iter().chain(&new).any(overlaps) {
+                skipped.push((fix, FixError::Conflict { line: number }));
+                continue 'fixes;
+            }
+
+            new.push(edit);
+        }
+
+        edits.extend(new);
+        applied.push(fix);
+    }
+
+    edits.sort_by_key(|&(start, end, _)| (start, end));
+
+    let mut fixed = String::with_capacity(source.len());
+    let mut last = 0;
+    for (start, end, replacement) in edits {
+        fixed.push_str(&source[last..start]);
+        fixed.push_str(&replacement);
+        last = end;
+    }
+    fixed.push_str(&source[last..]);
+
+    Applied {
+        source: fixed,
+        applied,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, fixes, Change, Fix, FixError};
+    use crate::{frackfile, Applicability};
+
+    /// The source after applying every fix in the `.frack` diagnostics, and
+    /// what was skipped.
+    fn fixed(source: &str, frack: &str) -> (String, Vec<String>) {
+        let diagnostics = frackfile::parse(frack).unwrap();
+        let fixes = fixes(&diagnostics);
+        let applied = apply(source, &fixes);
+        let skipped = applied.skipped.iter().map(|(_, e)| e.to_string()).collect();
+        (applied.source, skipped)
+    }
+
+    #[test]
+    fn several_changes_on_one_line() {
+        let frack = "\
+error: cannot assign twice to immutable variable
+ --> src/main.rs:1:5
+  |
+1 | let x = 1;
+  |     ^
+  |
+help: make it mutable
+  |
+1 | let mut x = 1u8;
+  |     ++++      ++
+";
+        let (source, skipped) = fixed("let x = 1;\n", frack);
+        assert_eq!(source, "let mut x = 1u8;\n");
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn overlapping_changes_in_one_fix_conflict() {
+        let change = |code: &str| Change {
+            line_number: 1,
+            original: None,
+            code: code.to_string(),
+        };
+        let fix = Fix {
+            path: "src/main.rs".to_string(),
+            message: "rename it".to_string(),
+            applicability: Applicability::MachineApplicable,
+            changes: vec![change("let y = 1;"), change("let z = 1;")],
+        };
+
+        let applied = apply("let x = 1;\n", [&fix]);
+        assert_eq!(applied.source, "let x = 1;\n");
+        assert!(applied.applied.is_empty());
+        assert!(matches!(
+            applied.skipped[..],
+            [(_, FixError::Conflict { line: 1 })]
+        ));
+    }
+
+    #[test]
+    fn later_fixes_that_overlap_are_skipped() {
+        let frack = "\
+error: unused variable
+ --> src/main.rs:1:5
+  |
+1 | let x = 1;
+  |     ^
+  |
+help: rename it
+  |
+1 | let _x = 1;
+  |     ~~
+help: remove it
+  |
+1 | let x = 1;
+  | ----------
+";
+        let (source, skipped) = fixed("let x = 1;\n", frack);
+        assert_eq!(source, "let _x = 1;\n");
+        assert_eq!(skipped, ["overlaps another fix on line 1"]);
+    }
+
+    #[test]
+    fn duplicate_fixes_apply_once() {
+        let frack = "\
+error: unused variable
+ --> src/main.rs:1:5
+  |
+1 | let x = 1;
+  |     ^
+  |
+help: rename it
+  |
+1 | let _x = 1;
+  |     ~~
+help: rename it again
+  |
+1 | let _x = 1;
+  |     ~~
+";
+        let (source, skipped) = fixed("let x = 1;\n", frack);
+        assert_eq!(source, "let _x = 1;\n");
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn out_of_date_fixes_are_skipped() {
+        let frack = "\
+error: unused variable
+ --> src/main.rs:1:5
+  |
+1 | let x = 1;
+  |     ^
+  |
+help: rename it
+  |
+1 | let _x = 1;
+  |     ~~
+";
+        let (source, skipped) = fixed("let y = 1;\n", frack);
+        assert_eq!(source, "let y = 1;\n");
+        assert_eq!(skipped, ["line 1 doesn't match what the diagnostic showed"]);
+    }
+}
@@ -0,0 +1,664 @@
+//! The `.frack` description format: diagnostics written out the way they're
+//! displayed.
+//!
+//! ```text
+//! # Lines starting with `#` are comments.
+//! error[E0308]: mismatched types
+//!  --> src/main.rs:2:18
+//!   |
+//! 2 |     let x: i32 = "hello";
+//!   |            ---   ^^^^^^^ expected `i32`, found `&str`
+//!   |
+//! help: try using a number
+//!   |
+//! 2 |     let x: i32 = 5;
+//!   |                  ~
+//! note: numbers aren't strings
+//! ```
+//!
+//! Each diagnostic starts with an `error[CODE]: `, `error: `, or `warning: `
+//! header, and is followed by, in order:
+//!
+//! - an optional ` --> path:line:col` location; without one, the location
+//!   isn't displayed
+//! - any number of code lines, `<line> | <code>`, each optionally followed by a
+//!   marker line, `| <underlines> <label>`, whose underlines are aligned with
+//!   the code above it; the label is the last underline's, and the labels of
+//!   the others go on the lines below, under their starts, after any `|`s
+//! - any number of `help: ` and `note: ` lines, in any order; each help can
+//!   be followed by the code lines of its suggestion
+//!
+//! A help or note indented two spaces further than the one before it is
+//! nested under it. A `warning: ` line is a child rather than a new
+//! diagnostic if it's indented or follows a `= `.
+//!
+//! The underline's symbol picks the kind of [`Marker`]: `^` for the primary
+//! span, `-` for secondary spans, `~` for replacements, and `+` for insertions.
+//! A label can start with one of those symbols as long as it isn't followed by
+//! a space, like the `-1` of `^ -1 is too small`.
+//!
+//! Blank lines, lone `|` gutters, and `...` are ignored, and `= help: ` and
+//! `= note: ` are accepted as well, so the [`Plain`](crate::Plain) rendering of
+//! a diagnostic is valid `.frack`:
+//!
+//! ```rust
+//! use frack::{frackfile, Plain};
+//!
+//! let source = r#"error[E0308]: mismatched types
+//!  --> src/main.rs:2:18
+//!   |
+//! 2 |     let x: i32 = "hello";
+//!   |            ---   ^^^^^^^ expected `i32`, found `&str`
+//!   |            |
+//!   |            expected due to this
+//!   |
+//! help: try using a number
+//!   |
+//! 2 |     let x: i32 = 5;
+//!   |                  ~ a number
+//! note: numbers aren't strings
+//! "#;
+//!
+//! let diagnostics = frackfile::parse(source).unwrap();
+//! assert_eq!(Plain(&diagnostics[0]).to_string(), source);
+//! ```
+//!
+//! ```rust,no_run
+//! let source = std::fs::read_to_string("error.frack").unwrap();
+//! match frack::frackfile::parse(&source) {
+//!     Ok(diagnostics) => diagnostics.iter().for_each(|d| println!("{d}")),
+//!     Err(e) => eprintln!("{}", e.error("error.frack", &source)),
+//! }
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::{edit, Child, ChildKind, Code, Diagnostic, Error, File, Help, Line, Marker, Warning};
+
+/// An error in a `.frack` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line of the error, starting at 1.
+    pub line: usize,
+
+    /// The columns of the error in that line, starting at 0, in characters.
+    pub range: RangeInclusive<usize>,
+
+    /// What went wrong.
+    pub message: String,
+
+    /// A label for the offending part of the line.
+    pub label: String,
+
+    /// How to fix it, if there's anything to say.
+    pub help: Option<String>,
+}
+
+impl ParseError {
+    /// The error as a frack [`Error`] pointing into the file, whose `path` and
+    /// contents are given.
+    pub fn error(&self, path: &str, source: &str) -> Error {
+        let code = source.lines().nth(self.line - 1).unwrap_or("");
+
+        Error {
+            error_code: String::new(),
+            message: self.message.clone(),
+            file: File {
+                path: path.to_string(),
+                line: self.line,
+                col: self.range.start() + 1,
+            },
+            code: Code::single(
+                code,
+                self.line,
+                Some(Marker {
+                    range: self.range.clone(),
+                    symbol: '^',
+                    color: 9,
+                    message: Some(self.label.clone()),
+                    color_span: false,
+                }),
+            ),
+            children: self.help.iter().map(Child::help).collect(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line,
+            self.range.start() + 1,
+            self.message,
+            self.label
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse the diagnostics in a `.frack` file.
+pub fn parse(source: &str) -> Result<Vec<Diagnostic>, ParseError> {
+    let mut parser = Parser {
+        diagnostics: Vec::new(),
+        current: None,
+    };
+
+    for (i, line) in source.lines().enumerate() {
+        parser.line(i + 1, line.trim_end_matches('\r'))?;
+    }
+
+    parser.finish();
+    Ok(parser.diagnostics)
+}
+
+/// Where the code lines being parsed go.
+#[derive(PartialEq)]
+enum Section {
+    Code,
+    Help,
+    Notes,
+}
+
+/// A diagnostic being parsed.
+struct Partial {
+    is_error: bool,
+    error_code: String,
+    message: String,
+    file: Option<File>,
+    code: Code,
+    children: Vec<Child>,
+    section: Section,
+}
+
+struct Parser {
+    diagnostics: Vec<Diagnostic>,
+    current: Option<Partial>,
+}
+
+impl Parser {
+    fn line(&mut self, number: usize, text: &str) -> Result<(), ParseError> {
+        let trimmed = text.trim_start();
+        let indent = text.chars().count() - trimmed.chars().count();
+        let err = |start: usize, len: usize, message: &str, label: &str| ParseError {
+            line: number,
+            range: indent + start..=indent + start + len.max(1) - 1,
+            message: message.to_string(),
+            label: label.to_string(),
+            help: None,
+        };
+
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "..." {
+            return Ok(());
+        }
+
+        // an indented `warning: ` is a child, not a new diagnostic
+        let header = header(trimmed).filter(|_| indent == 0 || self.current.is_none());
+        if let Some(header) = header {
+            self.finish();
+            let (is_error, error_code, message) =
+                header.map_err(|(start, len, message, label)| err(start, len, message, label))?;
+
+            self.current = Some(Partial {
+                is_error,
+                error_code,
+                message,
+                file: None,
+                code: Code(Vec::new()),
+                children: Vec::new(),
+                section: Section::Code,
+            });
+            return Ok(());
+        }
+
+        let len = trimmed.chars().count();
+        let Some(current) = &mut self.current else {
+            return Err(ParseError {
+                help: Some("start with `error: `, `error[CODE]: `, or `warning: `".to_string()),
+                ..err(0, len, "expected a diagnostic", "not in a diagnostic")
+            });
+        };
+
+        // nested children are indented two spaces per level, after the `= `
+        let (depth, child) = match trimmed.strip_prefix("= ") {
+            Some(rest) => {
+                let child = rest.trim_start();
+                (rest.len() - child.len(), child)
+            }
+            None => (indent, trimmed),
+        };
+
+        let kind = if let Some(message) = child.strip_prefix("help:") {
+            Some(Child::help(message.trim()))
+        } else if let Some(message) = child.strip_prefix("note:") {
+            Some(Child::note(message.trim()))
+        } else {
+            child
+                .strip_prefix("warning:")
+                .map(|message| Child::warning(message.trim()))
+        };
+
+        if let Some(child) = kind {
+            current.section = match child.kind {
+                ChildKind::Help(_) => Section::Help,
+                _ => Section::Notes,
+            };
+
+            let mut siblings = &mut current.children;
+            for _ in 0..depth / 2 {
+                if siblings.is_empty() {
+                    break;
+                }
+                siblings = &mut siblings.last_mut().unwrap().children;
+            }
+            siblings.push(child);
+            return Ok(());
+        }
+
+        if let Some(location) = trimmed.strip_prefix("-->") {
+            if current.file.is_some()
+                || !current.code.is_empty()
+                || current.section != Section::Code
+            {
+                return Err(ParseError {
+                    help: Some("put the location right after the header".to_string()),
+                    ..err(
+                        0,
+                        3,
+                        "unexpected location",
+                        "location must follow the header",
+                    )
+                });
+            }
+
+            let start = len - location.trim_start().chars().count();
+            current.file = Some(
+                file(location.trim())
+                    .map_err(|label| err(start, len - start, "invalid location", label))?,
+            );
+            return Ok(());
+        }
+
+        if let Some(rest) = trimmed.strip_prefix('|') {
+            let rest = rest.strip_prefix(' ').unwrap_or(rest);
+            if rest.trim().is_empty() {
+                return Ok(());
+            }
+
+            let (pipe, rest_len) = (len - rest.chars().count(), rest.chars().count());
+            let code = match current.section {
+                Section::Code => &mut current.code,
+                Section::Help => match &mut last_help(&mut current.children).suggestion {
+                    Some(code) => code,
+                    None => {
+                        return Err(err(pipe, rest_len, "unexpected marker", "no code to mark"))
+                    }
+                },
+                Section::Notes => {
+                    return Err(err(
+                        pipe,
+                        rest_len,
+                        "unexpected marker",
+                        "notes can't have code",
+                    ))
+                }
+            };
+
+            let Some(line) = code.last() else {
+                return Err(err(pipe, rest_len, "unexpected marker", "no code to mark"));
+            };
+
+            // the line's already marked, so this has the labels of its
+            // earlier markers
+            if line.marker.is_some() {
+                let line_number = line.line_number;
+                let marked = code.len()
+                    - code
+                        .iter()
+                        .rev()
+                        .take_while(|l| l.line_number == line_number)
+                        .count();
+                return label(&mut code[marked..], rest)
+                    .map_err(|(start, len, label)| err(pipe + start, len, "invalid label", label));
+            }
+
+            let markers = markers(rest, current.is_error)
+                .map_err(|(start, len, label)| err(pipe + start, len, "invalid marker", label))?;
+
+            // one line per marker, which are displayed together
+            let line = code.pop().unwrap();
+            for marker in markers {
+                code.push(Line {
+                    code: line.code.clone(),
+                    line_number: line.line_number,
+                    marker: Some(marker),
+                });
+            }
+            return Ok(());
+        }
+
+        if let Some((lno, code)) = trimmed.split_once('|') {
+            let lno = lno.trim_end();
+            if !lno.is_empty() && lno.bytes().all(|b| b.is_ascii_digit()) {
+                let line_number = lno.parse().unwrap_or(0);
+                if line_number == 0 {
+                    return Err(err(
+                        0,
+                        lno.len(),
+                        "invalid line number",
+                        "line numbers start at 1",
+                    ));
+                }
+
+                let line = Line {
+                    code: code.strip_prefix(' ').unwrap_or(code).to_string(),
+                    line_number,
+                    marker: None,
+                };
+
+                match current.section {
+                    Section::Code => current.code.push(line),
+                    Section::Help => last_help(&mut current.children)
+                        .suggestion
+                        .get_or_insert_with(|| Code(Vec::new()))
+                        .push(line),
+                    Section::Notes => {
+                        return Err(err(0, len, "unexpected code", "notes can't have code"))
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        Err(ParseError {
+            help: Some(
+                "expected a location, `<line> | <code>`, a marker, `help: `, or `note: `"
+                    .to_string(),
+            ),
+            ..err(0, len, "unrecognized line", "not part of a diagnostic")
+        })
+    }
+
+    fn finish(&mut self) {
+        let Some(p) = self.current.take() else {
+            return;
+        };
+
+        let file = p.file.unwrap_or_else(|| {
+            let primary = p
+                .code
+                .iter()
+                .find(|l| l.marker.as_ref().is_some_and(|m| m.symbol == '^'))
+                .or(p.code.first());
+
+            File {
+                path: String::new(),
+                line: primary.map_or(0, |l| l.line_number),
+                col: primary
+                    .and_then(|l| l.marker.as_ref())
+                    .map_or(0, |m| m.range.start() + 1),
+            }
+        });
+
+        self.diagnostics.push(if p.is_error {
+            Diagnostic::Error(Error {
+                error_code: p.error_code,
+                message: p.message,
+                file,
+                code: p.code,
+                children: p.children,
+            })
+        } else {
+            Diagnostic::Warning(Warning {
+                message: p.message,
+                file,
+                code: p.code,
+                children: p.children,
+            })
+        });
+    }
+}
+
+/// The most recently parsed child, which must be a help.
+///
+/// Children are only ever added at the end, so it's the last one at the
+/// deepest level.
+fn last_help(children: &mut [Child]) -> &mut Help {
+    let mut last = children.last_mut().unwrap();
+    while !last.children.is_empty() {
+        last = last.children.last_mut().unwrap();
+    }
+
+    match &mut last.kind {
+        ChildKind::Help(help) => help,
+        _ => unreachable!("code only follows helps"),
+    }
+}
+
+/// An error in a header: the start column, length, message, and label.
+type HeaderError = (usize, usize, &'static str, &'static str);
+
+/// Parse a diagnostic header, returning `None` if the line isn't one.
+fn header(line: &str) -> Option<Result<(bool, String, String), HeaderError>> {
+    let (is_error, rest) = if let Some(rest) = line.strip_prefix("error") {
+        (true, rest)
+    } else {
+        (false, line.strip_prefix("warning")?)
+    };
+
+    let level = line.len() - rest.len();
+    let (error_code, rest) = match rest.strip_prefix('[') {
+        Some(rest) => {
+            let Some((code, rest)) = rest.split_once(']') else {
+                return Some(Err((
+                    level,
+                    rest.chars().count() + 1,
+                    "invalid header",
+                    "unclosed `[`",
+                )));
+            };
+
+            if !is_error {
+                return Some(Err((
+                    level,
+                    code.chars().count() + 2,
+                    "invalid header",
+                    "warnings can't have codes",
+                )));
+            }
+
+            (code.to_string(), rest)
+        }
+        None => (String::new(), rest),
+    };
+
+    let message = rest.strip_prefix(':')?.trim();
+    if message.is_empty() {
+        let end = line.chars().count();
+        return Some(Err((end - 1, 1, "invalid header", "missing message")));
+    }
+
+    Some(Ok((is_error, error_code, message.to_string())))
+}
+
+/// Parse a `path:line:col` location.
+fn file(location: &str) -> Result<File, &'static str> {
+    let mut parts = location.rsplitn(3, ':');
+    let col = parts.next().unwrap_or("");
+    let (Some(line), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("expected `path:line:col`");
+    };
+
+    if path.is_empty() {
+        return Err("missing path");
+    }
+
+    let (Ok(line), Ok(col)) = (line.parse(), col.parse()) else {
+        return Err("line and column must be numbers");
+    };
+
+    Ok(File {
+        path: path.to_string(),
+        line,
+        col,
+    })
+}
+
+/// Parse the part of a marker line after `| ` into its markers, from left to
+/// right; errors are the start column, length, and label.
+///
+/// Each run of `^`, `-`, `~`, or `+` is a marker, and anything after the last
+/// one is its label. A run followed by anything else, like the `-` of
+/// `^^^ -1 is too small`, starts the label instead.
+fn markers(rest: &str, is_error: bool) -> Result<Vec<Marker>, (usize, usize, &'static str)> {
+    let chars: Vec<char> = rest.chars().collect();
+    let is_symbol = |c: &char| matches!(c, '^' | '-' | '~' | '+');
+    let mut markers: Vec<Marker> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let symbol = chars[i];
+        let run = chars[i..].iter().take_while(|&&c| c == symbol).count();
+        let after = chars.get(i + run).filter(|c| **c != ' ' && !is_symbol(c));
+        if !markers.is_empty() && chars[i - 1] == ' ' && after.is_some() {
+            break;
+        }
+
+        let (color, color_span) = match symbol {
+            ' ' => {
+                i += 1;
+                continue;
+            }
+            '^' if is_error => (9, false),
+            '^' => (3, false),
+            '-' => (12, false),
+            '~' | '+' => (10, true),
+            _ => break,
+        };
+
+        markers.push(Marker {
+            range: i..=i + run - 1,
+            symbol,
+            color,
+            message: None,
+            color_span,
+        });
+        i += run;
+    }
+
+    let Some(last) = markers.last_mut() else {
+        let start = chars.iter().take_while(|&&c| c == ' ').count();
+        return Err((start, 1, "expected `^`, `-`, `~`, or `+`"));
+    };
+
+    let label: String = chars[i..].iter().collect();
+    if !label.is_empty() {
+        if chars[i - 1] != ' ' {
+            return Err((i, 1, "expected a space before the label"));
+        }
+
+        last.message = Some(label.trim_end().to_string());
+    }
+
+    Ok(markers)
+}
+
+/// Parse a line under a marker line: `|` connectors, possibly followed by the
+/// label of the marker starting in the same column. Errors are like
+/// [`markers`]'.
+fn label(lines: &mut [Line], rest: &str) -> Result<(), (usize, usize, &'static str)> {
+    let Some((byte, _)) = rest.char_indices().find(|&(_, c)| c != ' ' && c != '|') else {
+        return Ok(());
+    };
+
+    let column = edit::column(rest, byte);
+    let label = rest[byte..].trim_end();
+    let len = label.chars().count();
+    let marker = lines
+        .iter_mut()
+        .filter_map(|l| l.marker.as_mut())
+        .find(|m| *m.range.start() == column);
+
+    match marker {
+        Some(m) if m.message.is_none() => {
+            m.message = Some(label.to_string());
+            Ok(())
+        }
+        Some(_) => Err((column, len, "that marker already has a label")),
+        None => Err((column, len, "not under the start of a marker")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::{Diagnostic, Plain};
+
+    fn render(diagnostics: &[Diagnostic]) -> String {
+        diagnostics.iter().map(|d| Plain(d).to_string()).collect()
+    }
+
+    /// The range, symbol, and label of each marker of the diagnostic's code.
+    fn markers(diag: &Diagnostic) -> Vec<(usize, usize, char, Option<&str>)> {
+        let markers = diag.code().iter().filter_map(|l| l.marker.as_ref());
+        markers
+            .map(|m| (*m.range.start(), *m.range.end(), m.symbol, m.label()))
+            .collect()
+    }
+
+    #[test]
+    fn rendering_round_trips() {
+        for source in [
+            "error[E0308]: mismatched types\n --> src/main.rs:2:18\n  |\n2 |     let x: i32 = \"hello\";\n  |            ---   ^^^^^^^ expected `i32`\n  |            |\n  |            expected due to this\n",
+            "warning: unused variable\n --> src/main.rs:1:5\n  |\n1 | let x = 1;\n  |     ^\n  |\nhelp: prefix it\n  |\n1 | let _x = 1;\n  |     ~~\n",
+            "error: three labels\n  |\n1 | let x = foo(1, 2);\n  | ^^^ ^   ---   ~ d\n  | |   |   |\n  | |   |   c\n  | |   b\n  | a\n",
+            "error: adjacent\n  |\n1 | let x = 1;\n  | ^^^---+++ label\n",
+            "error: symbols in labels\n  |\n1 | let x = 1;\n  |     ^ -1 is too small\n",
+            "error: symbols in labels\n  |\n1 | let x = 1;\n  | ^^^ ~~~ -- +1\n  | |   |\n  | |   ~ish\n  | a\n",
+        ] {
+            let once = render(&parse(source).unwrap());
+            let reparsed = parse(&once).unwrap();
+            assert_eq!(render(&reparsed), once, "{source}");
+        }
+    }
+
+    #[test]
+    fn labels_can_start_with_marker_symbols() {
+        let diag = parse("error: x\n  |\n1 | let x = 1;\n  |     ^ -1 is too small\n").unwrap();
+        assert_eq!(markers(&diag[0]), [(4, 4, '^', Some("-1 is too small"))]);
+
+        // but a run of symbols followed by a space is another marker
+        let diag = parse("error: x\n  |\n1 | let x = 1;\n  | ^^^ - a\n").unwrap();
+        assert_eq!(
+            markers(&diag[0]),
+            [(0, 2, '^', None), (4, 4, '-', Some("a"))]
+        );
+    }
+
+    #[test]
+    fn unusual_labels_round_trip() {
+        for label in ["-1", "+1 more", "~ish", "^_^", ""] {
+            let source = "error: x\n  |\n1 | let x = 1;\n  | ^^^ ---\n";
+            let mut diag = parse(source).unwrap();
+            let Diagnostic::Error(error) = &mut diag[0] else {
+                unreachable!()
+            };
+            for line in error.code.iter_mut() {
+                line.marker.as_mut().unwrap().message = Some(label.to_string());
+            }
+
+            let expected = match label {
+                "" => None,
+                label => Some(label),
+            };
+            let reparsed = parse(&render(&diag)).unwrap();
+            assert_eq!(
+                markers(&reparsed[0]),
+                [(0, 2, '^', expected), (4, 6, '-', expected)],
+                "{label:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Opt-in syntax highlighting for code blocks.
+//!
+//! `rustc` doesn't color the code itself, so neither does frack by default.
+//! To highlight, wrap a diagnostic in [`Highlighted`] with a [`Highlight`]er,
+//! such as the built-in [`Rust`] one:
+//!
+//! ```rust,no_run
+//! use frack::highlight::{Highlighted, Rust};
+//!
+//! let error = frack::error! {
+//!     "E0308", "mismatched types";
+//!     "src/main.rs", 2, 18;
+//!     "    let x: u8 = \"five\"; // oops";
+//!     "`\"five\"`" => "expected `u8`, found `&str`";
+//! };
+//!
+//! println!("{}", Highlighted::new(&error, &Rust));
+//! ```
+//!
+//! Highlighting only adds colors, so markers stay aligned, and the colors of
+//! [`color_span`](crate::Marker::color_span) markers go over it.
+
+use std::{fmt, ops::Range};
+
+use crate::{Diagnostic, Error, Warning};
+
+/// The kinds of token a [`Highlight`]er can pick out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Keyword,
+
+    /// String, character, and byte literals.
+    String,
+
+    Comment,
+    Lifetime,
+    Number,
+}
+
+/// A syntax highlighter for some language.
+pub trait Highlight {
+    /// The tokens to color in a line of code, as byte ranges.
+    ///
+    /// Lines are highlighted on their own, so constructs that span lines, like
+    /// block comments, are only highlighted on the line they start on.
+    fn tokens(&self, line: &str) -> Vec<(Range<usize>, Kind)>;
+
+    /// The [ANSI escape color](https://wikipedia.org/wiki/ANSI_escape_code#8-bit)
+    /// of a kind of token, or `None` to leave it uncolored.
+    fn color(&self, kind: Kind) -> Option<u8> {
+        Some(match kind {
+            Kind::Keyword => 5,
+            Kind::String => 2,
+            Kind::Comment => 8,
+            Kind::Lifetime => 6,
+            Kind::Number => 3,
+        })
+    }
+}
+
+/// A diagnostic, displayed with its code highlighted.
+///
+/// To display using ANSI escape codes, use the [`Display`](fmt::Display) impl.
+pub struct Highlighted<'a, T> {
+    pub inner: &'a T,
+    pub highlighter: &'a dyn Highlight,
+}
+
+impl<'a, T> Highlighted<'a, T> {
+    pub fn new(inner: &'a T, highlighter: &'a dyn Highlight) -> Self {
+        Self { inner, highlighter }
+    }
+}
+
+impl fmt::Display for Highlighted<'_, Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.display_with(Some(self.highlighter), f)
+    }
+}
+
+impl fmt::Display for Highlighted<'_, Warning> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.display_with(Some(self.highlighter), f)
+    }
+}
+
+impl fmt::Display for Highlighted<'_, Diagnostic> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inner {
+            Diagnostic::Error(e) => e.display_with(Some(self.highlighter), f),
+            Diagnostic::Warning(w) => w.display_with(Some(self.highlighter), f),
+        }
+    }
+}
+
+/// A highlighter for Rust.
+///
+/// ```rust
+/// use frack::highlight::{Highlight, Kind, Rust};
+///
+/// let line = "let s: &'a str = \"hi\"; // 42";
+/// let kinds: Vec<_> = Rust.tokens(line).into_iter().map(|(r, k)| (&line[r], k)).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         ("let", Kind::Keyword),
+///         ("'a", Kind::Lifetime),
+///         ("\"hi\"", Kind::String),
+///         ("// 42", Kind::Comment),
+///     ]
+/// );
+/// ```
+pub struct Rust;
+
+/// Rust's strict keywords, and `true` and `false`.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+impl Highlight for Rust {
+    fn tokens(&self, line: &str) -> Vec<(Range<usize>, Kind)> {
+        let bytes = line.as_bytes();
+        let at = |i: usize| bytes.get(i).copied().unwrap_or(0);
+        let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80;
+        let ident_end = |mut i: usize| {
+            while i < bytes.len() && is_ident(bytes[i]) {
+                i += 1;
+            }
+            i
+        };
+
+        // the end of a string whose opening quote is at `i`
+        let string_end = |i: usize| {
+            let mut j = i + 1;
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'\\' => j += 2,
+                    b'"' => return j + 1,
+                    _ => j += 1,
+                }
+            }
+            bytes.len()
+        };
+
+        // the end of a raw string whose `r` is at `i`, or `None` if it isn't one
+        let raw_end = |i: usize| {
+            let hashes = bytes[i + 1..].iter().take_while(|&&b| b == b'#').count();
+            let open = i + 1 + hashes;
+            if at(open) != b'"' {
+                return None;
+            }
+
+            let close = format!("\"{}", "#".repeat(hashes));
+            Some(
+                line[open + 1..]
+                    .find(&close)
+                    .map_or(line.len(), |j| open + 1 + j + close.len()),
+            )
+        };
+
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let start = i;
+            let b = bytes[i];
+            let (end, kind) = match b {
+                b'/' if at(i + 1) == b'/' => (bytes.len(), Some(Kind::Comment)),
+                b'/' if at(i + 1) == b'*' => {
+                    // block comments nest
+                    let mut depth = 0;
+                    let mut j = i;
+                    while j < bytes.len() {
+                        match (bytes[j], at(j + 1)) {
+                            (b'/', b'*') => depth += 1,
+                            (b'*', b'/') => depth -= 1,
+                            _ => {
+                                j += 1;
+                                continue;
+                            }
+                        }
+
+                        j += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    (j.min(bytes.len()), Some(Kind::Comment))
+                }
+                b'"' => (string_end(i), Some(Kind::String)),
+                b'\'' => {
+                    // a char literal is `'\...'` or `'x'`; anything else is a
+                    // lifetime or label
+                    let next = line[i + 1..].chars().next().map_or(0, char::len_utf8);
+                    if at(i + 1) == b'\\' {
+                        let end = line
+                            .get(i + 3..)
+                            .and_then(|rest| rest.find('\''))
+                            .map_or(line.len(), |j| i + 4 + j);
+                        (end, Some(Kind::String))
+                    } else if next > 0 && at(i + 1 + next) == b'\'' {
+                        (i + 2 + next, Some(Kind::String))
+                    } else if is_ident(at(i + 1)) {
+                        (ident_end(i + 1), Some(Kind::Lifetime))
+                    } else {
+                        (i + 1, None)
+                    }
+                }
+                b'0'..=b'9' => {
+                    let is_exponent =
+                        |j: usize| matches!(bytes[j], b'e' | b'E') && !line[i..].starts_with("0x");
+                    let mut j = i + 1;
+                    while j < bytes.len() {
+                        match bytes[j] {
+                            b'.' if at(j + 1).is_ascii_digit() => j += 1,
+                            b'+' | b'-' if is_exponent(j - 1) => j += 1,
+                            b if is_ident(b) && b < 0x80 => j += 1,
+                            _ => break,
+                        }
+                    }
+                    (j, Some(Kind::Number))
+                }
+                _ if is_ident(b) => {
+                    let end = ident_end(i);
+                    let word = &line[i..end];
+                    match (word, at(end)) {
+                        ("r" | "br" | "cr", b'"' | b'#') => match raw_end(end - 1) {
+                            Some(end) => (end, Some(Kind::String)),
+                            None => (end, None),
+                        },
+                        ("b" | "c", b'"') => (string_end(end), Some(Kind::String)),
+                        ("b", b'\'') => {
+                            let close = line[end + 1..]
+                                .find('\'')
+                                .map_or(line.len(), |j| end + 2 + j);
+                            (close, Some(Kind::String))
+                        }
+                        _ if KEYWORDS.contains(&word) && !line[..i].ends_with("r#") => {
+                            (end, Some(Kind::Keyword))
+                        }
+                        _ => (end, None),
+                    }
+                }
+                _ => (i + line[i..].chars().next().map_or(1, char::len_utf8), None),
+            };
+
+            if let Some(kind) = kind {
+                tokens.push((start..end, kind));
+            }
+            i = end;
+        }
+
+        tokens
+    }
+}
@@ -0,0 +1,454 @@
+//! A static HTML error index, like `rustc`'s.
+//!
+//! Generates one page per error code, plus a searchable table of contents. The
+//! output is self-contained: no network access or external tooling needed to
+//! build or view it.
+//!
+//! ```rust,no_run
+//! frack::codes! {
+//!     static CODES = "eggtool";
+//!     E0001 => "# Rotten eggs\n\nAn egg was used after it had gone bad.",
+//! }
+//!
+//! let error = frack::error! {
+//!     "E0001", "this code smells bad";
+//!     "src/main.rs", 12, 3;
+//!     "  rotten(eggs);";
+//!     2..=13;
+//! };
+//!
+//! let mut index = frack::index::Index::new(&CODES);
+//! index.example("E0001", &error);
+//! index.write("target/error-index").unwrap();
+//! ```
+
+use std::fmt::{self, Write as _};
+use std::io;
+use std::path::Path;
+
+use crate::codes::{Entry, Registry};
+use crate::markdown::{parse, spans, Block, Span};
+
+/// A static HTML site documenting a [`Registry`].
+pub struct Index<'a> {
+    registry: &'a Registry,
+    title: String,
+    examples: Vec<(&'static str, String)>,
+}
+
+impl<'a> Index<'a> {
+    /// Create an index of every code in `registry`.
+    pub fn new(registry: &'a Registry) -> Self {
+        Self {
+            registry,
+            title: format!("{} error codes index", registry.tool),
+            examples: Vec::new(),
+        }
+    }
+
+    /// Set the title of the site.
+    pub fn title(mut self, title: impl ToString) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Add an example diagnostic to the page for `code`.
+    ///
+    /// The example is rendered using its [`Display`](fmt::Display) impl, with
+    /// its ANSI escape codes translated to HTML. Examples for unregistered
+    /// codes are ignored.
+    pub fn example(&mut self, code: &str, example: &impl fmt::Display) {
+        if let Some(entry) = self.registry.entry(code) {
+            let rendered = ansi_to_html(&example.to_string());
+            self.examples.push((entry.code, rendered));
+        }
+    }
+
+    /// Render every page of the site, as `(file name, contents)` pairs.
+    pub fn pages(&self) -> Vec<(String, String)> {
+        let mut pages = vec![
+            ("index.html".to_string(), self.toc()),
+            ("style.css".to_string(), STYLE.to_string()),
+        ];
+
+        let entries = self.registry.entries;
+        for (i, entry) in entries.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|i| &entries[i]);
+            let next = entries.get(i + 1);
+            pages.push((format!("{}.html", entry.code), self.page(entry, prev, next)));
+        }
+
+        pages
+    }
+
+    /// Write every page of the site into `dir`, creating it if needed.
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for (name, contents) in self.pages() {
+            std::fs::write(dir.join(name), contents)?;
+        }
+
+        Ok(())
+    }
+
+    fn toc(&self) -> String {
+        let mut items = String::new();
+        for entry in self.registry.entries {
+            let _ = writeln!(
+                items,
+                "<li data-search=\"{}\"><a href=\"{code}.html\"><code>{code}</code></a> {}</li>",
+                escape_html(&search_text(entry)),
+                escape_html(&summary(entry)),
+                code = entry.code,
+            );
+        }
+
+        let title = escape_html(&self.title);
+        format!(
+            "{HEAD}<title>{title}</title>
+</head>
+<body>
+<main>
+<h1>{title}</h1>
+<input id=\"search\" type=\"search\" placeholder=\"Search error codes and explanations...\" autofocus>
+<ul id=\"codes\">
+{items}</ul>
+<p id=\"empty\" hidden>No matching error codes.</p>
+</main>
+<script>{SEARCH}</script>
+</body>
+</html>
+"
+        )
+    }
+
+    fn page(&self, entry: &Entry, prev: Option<&Entry>, next: Option<&Entry>) -> String {
+        let mut body = self.link_codes(&to_html(entry.explanation));
+
+        let examples: Vec<_> = self
+            .examples
+            .iter()
+            .filter(|(code, _)| *code == entry.code)
+            .collect();
+        if !examples.is_empty() {
+            body.push_str("<h2>Example output</h2>\n");
+            for (_, example) in examples {
+                let _ = writeln!(body, "<pre class=\"diagnostic\">{example}</pre>");
+            }
+        }
+
+        let mut nav = String::from("<nav>");
+        if let Some(prev) = prev {
+            let _ = write!(nav, "<a href=\"{0}.html\">&larr; {0}</a> ", prev.code);
+        }
+        nav.push_str("<a href=\"index.html\">Index</a>");
+        if let Some(next) = next {
+            let _ = write!(nav, " <a href=\"{0}.html\">{0} &rarr;</a>", next.code);
+        }
+        nav.push_str("</nav>");
+
+        format!(
+            "{HEAD}<title>{code} - {title}</title>
+</head>
+<body>
+<main>
+{nav}
+<h1 class=\"code\">Error code {code}</h1>
+{body}{nav}
+</main>
+</body>
+</html>
+",
+            code = entry.code,
+            title = escape_html(&self.title),
+        )
+    }
+
+    /// Turn `<code>E0001</code>` into a link, for every registered code.
+    fn link_codes(&self, html: &str) -> String {
+        let mut html = html.to_string();
+        for entry in self.registry.entries {
+            let code = format!("<code>{}</code>", entry.code);
+            html = html.replace(
+                &code,
+                &format!("<a href=\"{}.html\">{code}</a>", entry.code),
+            );
+        }
+        html
+    }
+}
+
+/// The first line of an explanation, without Markdown syntax.
+fn summary(entry: &Entry) -> String {
+    entry
+        .explanation
+        .lines()
+        .map(|l| l.trim().trim_start_matches('#').trim())
+        .find(|l| !l.is_empty() && !l.starts_with("```"))
+        .unwrap_or("")
+        .replace(['`', '*'], "")
+}
+
+/// The text to match search terms against.
+fn search_text(entry: &Entry) -> String {
+    let text = format!("{} {}", entry.code, entry.explanation).to_lowercase();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Translate the ANSI escape codes used by frack into HTML.
+fn ansi_to_html(s: &str) -> String {
+    let mut out = String::new();
+    let mut open = String::new();
+    let mut style = String::new();
+    let (mut bold, mut italic, mut underline, mut fg) = (false, false, false, None);
+
+    let text = |out: &mut String, open: &mut String, style: &str, text: &str| {
+        if text.is_empty() {
+            return;
+        }
+
+        if open != style {
+            if !open.is_empty() {
+                out.push_str("</span>");
+            }
+            if !style.is_empty() {
+                let _ = write!(out, "<span style=\"{style}\">");
+            }
+            *open = style.to_string();
+        }
+
+        out.push_str(&escape_html(text));
+    };
+
+    let mut rest = s;
+    while let Some(i) = rest.find("\x1b[") {
+        text(&mut out, &mut open, &style, &rest[..i]);
+        rest = &rest[i + 2..];
+
+        let Some(end) = rest.find(|c: char| c.is_ascii_alphabetic()) else {
+            break;
+        };
+        let (params, after) = rest.split_at(end);
+        rest = &after[1..];
+        if !after.starts_with('m') {
+            continue;
+        }
+
+        let mut params = params.split(';').map(|p| p.parse::<u8>().unwrap_or(0));
+        while let Some(p) = params.next() {
+            match p {
+                0 => (bold, italic, underline, fg) = (false, false, false, None),
+                1 => bold = true,
+                3 => italic = true,
+                4 => underline = true,
+                38 if params.next() == Some(5) => fg = params.next(),
+                _ => {}
+            }
+        }
+
+        style.clear();
+        if bold {
+            style.push_str("font-weight:bold;");
+        }
+        if italic {
+            style.push_str("font-style:italic;");
+        }
+        if underline {
+            style.push_str("text-decoration:underline;");
+        }
+        if let Some(c) = fg {
+            let _ = write!(style, "color:{};", css_color(c));
+        }
+    }
+
+    text(&mut out, &mut open, &style, rest);
+    if !open.is_empty() {
+        out.push_str("</span>");
+    }
+
+    out
+}
+
+/// The CSS equivalent of an 8-bit ANSI color.
+fn css_color(c: u8) -> String {
+    const BASIC: [&str; 16] = [
+        "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+        "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+    ];
+
+    match c {
+        0..=15 => BASIC[c as usize].to_string(),
+        16..=231 => {
+            let c = c - 16;
+            let level = |v: u8| if v == 0 { 0 } else { v * 40 + 55 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(c / 36),
+                level(c / 6 % 6),
+                level(c % 6)
+            )
+        }
+        232..=255 => {
+            let v = (c - 232) * 10 + 8;
+            format!("#{v:02x}{v:02x}{v:02x}")
+        }
+    }
+}
+
+const HEAD: &str = "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<link rel=\"stylesheet\" href=\"style.css\">
+";
+
+const SEARCH: &str = "
+const search = document.getElementById('search');
+const items = document.querySelectorAll('#codes li');
+const empty = document.getElementById('empty');
+search.addEventListener('input', () => {
+    const terms = search.value.toLowerCase().split(/\\s+/).filter(t => t);
+    let shown = 0;
+    for (const item of items) {
+        const hit = terms.every(t => item.dataset.search.includes(t));
+        item.hidden = !hit;
+        shown += hit;
+    }
+    empty.hidden = shown !== 0;
+});
+";
+
+const STYLE: &str = "body {
+    margin: 0;
+    background: #ffffff;
+    color: #1e1e1e;
+    font-family: system-ui, sans-serif;
+    line-height: 1.5;
+}
+
+main {
+    max-width: 56rem;
+    margin: 0 auto;
+    padding: 1rem 2rem;
+}
+
+a {
+    color: #2472c8;
+}
+
+code, pre {
+    font-family: ui-monospace, monospace;
+}
+
+pre {
+    padding: 0.75rem 1rem;
+    overflow-x: auto;
+    background: #f5f5f5;
+    border-radius: 4px;
+}
+
+pre.diagnostic {
+    background: #1e1e1e;
+    color: #e5e5e5;
+}
+
+nav {
+    margin: 1rem 0;
+}
+
+#search {
+    width: 100%;
+    padding: 0.5rem;
+    font-size: 1rem;
+    box-sizing: border-box;
+}
+
+#codes {
+    padding: 0;
+    list-style: none;
+}
+
+#codes li {
+    padding: 0.25rem 0;
+}
+";
+
+/// Render Markdown to an HTML fragment.
+///
+/// Fenced code blocks become `<pre><code>` elements, with the fence's info
+/// string (if any) as a `language-*` class.
+fn to_html(md: &str) -> String {
+    let mut out = String::new();
+    for block in parse(md) {
+        match block {
+            Block::Heading(level, text) => {
+                let _ = writeln!(out, "<h{level}>{}</h{level}>", inline_html(&text));
+            }
+
+            Block::Paragraph(text) => {
+                let _ = writeln!(out, "<p>{}</p>", inline_html(&text));
+            }
+
+            Block::Fence(lang, code) => {
+                out.push_str("<pre><code");
+                if let Some(lang) = lang.split([',', ' ']).next().filter(|l| !l.is_empty()) {
+                    let _ = write!(out, " class=\"language-{}\"", escape_html(lang));
+                }
+                let _ = writeln!(out, ">{}</code></pre>", escape_html(&code));
+            }
+
+            Block::List(items) => {
+                let tag = if items.first().is_some_and(|(n, _)| n.is_some()) {
+                    "ol"
+                } else {
+                    "ul"
+                };
+
+                let _ = writeln!(out, "<{tag}>");
+                for (_, item) in items {
+                    let _ = writeln!(out, "<li>{}</li>", inline_html(&item));
+                }
+                let _ = writeln!(out, "</{tag}>");
+            }
+        }
+    }
+
+    out
+}
+
+/// Escape text for inclusion in HTML.
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn inline_html(text: &str) -> String {
+    let mut out = String::new();
+    for span in spans(text) {
+        let _ = match span {
+            Span::Text(t) => write!(out, "{}", escape_html(t)),
+            Span::Code(c) => write!(out, "<code>{}</code>", escape_html(c)),
+            Span::Strong(s) => write!(out, "<strong>{}</strong>", escape_html(s)),
+            Span::Emph(e) => write!(out, "<em>{}</em>", escape_html(e)),
+            Span::Link(t, url) => write!(
+                out,
+                "<a href=\"{}\">{}</a>",
+                escape_html(url),
+                escape_html(t)
+            ),
+        };
+    }
+    out
+}
@@ -0,0 +1,1061 @@
+//! `rustc`'s JSON diagnostic format, as emitted by `--error-format=json`.
+//!
+//! Diagnostics can be serialized with [`to_json`], and read back (from `rustc`
+//! or from `cargo --message-format=json`) with [`Stream`].
+//!
+//! ```rust,no_run
+//! let error = frack::error! {
+//!     "E0308", "mismatched types";
+//!     "src/main.rs", 2, 18;
+//!     "    let x: i32 = \"hello\";";
+//!     17..=23 => "expected `i32`, found `&str`";
+//! };
+//!
+//! let json = frack::json::to_json(&error.into());
+//! println!("{json}");
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+use crate::codes::Registry;
+use crate::{
+    edit, Applicability, Child, ChildKind, Code, Diagnostic, Error, File, Help, Line, Marker, Note,
+    Plain, SuggestionStyle, Warning,
+};
+
+/// A JSON value.
+///
+/// The [`Display`](fmt::Display) impl writes it compactly, on a single line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+
+    /// An object, with its keys in order.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Create an object from its keys and values.
+    pub fn object<const N: usize>(fields: [(&str, Value); N]) -> Self {
+        Self::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    /// Get a field of an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get a number, if it's a non-negative integer.
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Self::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self {
+        Self::Number(n as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Self::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "null"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::Number(n) if n.is_finite() => write!(f, "{n}"),
+            Self::Number(_) => write!(f, "null"),
+            Self::String(s) => write_string(s, f),
+            Self::Array(a) => {
+                write!(f, "[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                write!(f, "]")
+            }
+            Self::Object(o) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in o.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(k, f)?;
+                    write!(f, ":{v}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Serialize a diagnostic to `rustc`'s JSON format.
+///
+/// Byte offsets are computed as if the file contained only the lines shown in
+/// the diagnostic, with every other line empty; to get exact offsets, use
+/// [`Serializer::source`].
+pub fn to_json(diag: &Diagnostic) -> Value {
+    Serializer::new().diagnostic(diag)
+}
+
+/// Serializes diagnostics to `rustc`'s JSON format, with extra information.
+#[derive(Default)]
+pub struct Serializer<'a> {
+    source: Option<&'a str>,
+    registry: Option<&'a Registry>,
+}
+
+impl<'a> Serializer<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the contents of the file to compute byte offsets.
+    pub fn source(mut self, source: &'a str) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Fill in `code.explanation` from a registry.
+    pub fn registry(mut self, registry: &'a Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Serialize a single diagnostic.
+    pub fn diagnostic(&self, diag: &Diagnostic) -> Value {
+        let offsets = Offsets::new(self.source, diag);
+        let path = &diag.file().path;
+        let primary = diag.primary();
+
+        let spans = diag
+            .code()
+            .iter()
+            .filter_map(|line| {
+                let m = line.marker.as_ref()?;
+                Some(offsets.span(
+                    path,
+                    line,
+                    edit::marked(line, &m.range),
+                    primary.is_some_and(|p| std::ptr::eq(p, line)),
+                    m.message.clone(),
+                    None,
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        let children = children(diag.children(), diag.code(), path, &offsets);
+
+        let code = diag.error_code().map(|c| {
+            let explanation = self.registry.and_then(|r| r.explain(c));
+            Value::object([("code", c.into()), ("explanation", explanation.into())])
+        });
+
+        Value::object([
+            ("$message_type", "diagnostic".into()),
+            ("message", diag.message().into()),
+            ("code", code.into()),
+            ("level", diag.level().to_string().into()),
+            ("spans", spans.into()),
+            ("children", Value::Array(children)),
+            ("rendered", Plain(diag).to_string().into()),
+        ])
+    }
+}
+
+/// Serialize `children` of a diagnostic about `code`, in order.
+fn children(children: &[Child], code: &Code, path: &str, offsets: &Offsets) -> Vec<Value> {
+    children
+        .iter()
+        .map(|c| {
+            let (level, message, spans) = match &c.kind {
+                ChildKind::Help(help) => {
+                    let spans = help
+                        .suggestion
+                        .iter()
+                        .flat_map(|s| edit::suggested(code, s))
+                        .map(|(old, e)| {
+                            let suggestion = Some((e.replacement, help.applicability));
+                            offsets.span(path, old, e.range, true, None, suggestion)
+                        });
+                    ("help", &help.message, spans.collect())
+                }
+                ChildKind::Note(note) => ("note", &note.0, Vec::new()),
+                ChildKind::Warning(note) => ("warning", &note.0, Vec::new()),
+            };
+
+            Value::object([
+                ("$message_type", "diagnostic".into()),
+                ("message", message.as_str().into()),
+                ("code", Value::Null),
+                ("level", level.into()),
+                ("spans", spans.into()),
+                (
+                    "children",
+                    Value::Array(self::children(&c.children, code, path, offsets)),
+                ),
+                ("rendered", Value::Null),
+            ])
+        })
+        .collect()
+}
+
+/// Computes byte offsets of lines in a file.
+struct Offsets {
+    /// Byte offset of the start of each line, indexed by line number.
+    starts: Vec<usize>,
+
+    /// The lengths of known lines, if there's no source.
+    known: HashMap<usize, usize>,
+}
+
+impl Offsets {
+    fn new(source: Option<&str>, diag: &Diagnostic) -> Self {
+        let mut starts = vec![0, 0];
+        let mut known = HashMap::new();
+        match source {
+            Some(source) => {
+                for line in source.split_inclusive('\n') {
+                    starts.push(starts.last().unwrap() + line.len());
+                }
+            }
+
+            None => {
+                for line in diag.code().iter() {
+                    known.insert(line.line_number, line.code.len());
+                }
+            }
+        }
+
+        Self { starts, known }
+    }
+
+    fn line_start(&self, line_number: usize) -> usize {
+        if let Some(&start) = self
+            .starts
+            .get(line_number)
+            .filter(|_| self.known.is_empty())
+        {
+            return start;
+        }
+
+        (1..line_number)
+            .map(|l| self.known.get(&l).map_or(1, |len| len + 1))
+            .sum()
+    }
+
+    fn span(
+        &self,
+        path: &str,
+        line: &Line,
+        range: Range<usize>,
+        primary: bool,
+        label: Option<String>,
+        suggestion: Option<(String, Applicability)>,
+    ) -> Value {
+        let start = self.line_start(line.line_number);
+        let column = |byte| edit::column(&line.code, byte) + 1;
+        let (replacement, applicability) = suggestion.map(|(r, a)| (r, a.to_string())).unzip();
+
+        Value::object([
+            ("file_name", path.into()),
+            ("byte_start", (start + range.start).into()),
+            ("byte_end", (start + range.end).into()),
+            ("line_start", line.line_number.into()),
+            ("line_end", line.line_number.into()),
+            ("column_start", column(range.start).into()),
+            ("column_end", column(range.end).into()),
+            ("is_primary", primary.into()),
+            (
+                "text",
+                Value::Array(vec![Value::object([
+                    ("text", line.code.as_str().into()),
+                    ("highlight_start", column(range.start).into()),
+                    ("highlight_end", column(range.end).into()),
+                ])]),
+            ),
+            ("label", label.into()),
+            ("suggested_replacement", replacement.into()),
+            ("suggestion_applicability", applicability.into()),
+            ("expansion", Value::Null),
+        ])
+    }
+}
+
+/// Why a string isn't valid JSON.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The byte offset the error occurred at.
+    pub offset: usize,
+
+    pub message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a JSON value.
+pub fn parse(s: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser { s, i: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.i != s.len() {
+        return Err(parser.error("trailing characters"));
+    }
+
+    Ok(value)
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    i: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError {
+            offset: self.i,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.i).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.i += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.s[self.i..].starts_with(token);
+        if found {
+            self.i += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str, message: &'static str) -> Result<(), ParseError> {
+        self.whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.i += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if !self.eat("}") {
+                    loop {
+                        self.whitespace();
+                        if self.peek() != Some(b'"') {
+                            return Err(self.error("expected string key"));
+                        }
+                        let key = self.string()?;
+                        self.expect(":", "expected `:`")?;
+                        fields.push((key, self.value()?));
+
+                        self.whitespace();
+                        if self.eat("}") {
+                            break;
+                        }
+                        self.expect(",", "expected `,` or `}`")?;
+                    }
+                }
+                Ok(Value::Object(fields))
+            }
+
+            Some(b'[') => {
+                self.i += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.value()?);
+                        self.whitespace();
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",", "expected `,` or `]`")?;
+                    }
+                }
+                Ok(Value::Array(items))
+            }
+
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ if self.eat("null") => Ok(Value::Null),
+            _ if self.eat("true") => Ok(Value::Bool(true)),
+            _ if self.eat("false") => Ok(Value::Bool(false)),
+            None => Err(self.error("unexpected end of input")),
+            _ => Err(self.error("expected value")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.i;
+        while self
+            .peek()
+            .is_some_and(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))
+        {
+            self.i += 1;
+        }
+
+        self.s[start..self.i]
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| ParseError {
+                offset: start,
+                message: "invalid number",
+            })
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.i += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.s[self.i..];
+            let Some(end) = rest.find(['"', '\\']) else {
+                self.i = self.s.len();
+                return Err(self.error("unterminated string"));
+            };
+
+            out.push_str(&rest[..end]);
+            self.i += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(out);
+            }
+
+            let escape = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.i += 1;
+            match escape {
+                b'"' => out.push('"'),
+                b'\\' => out.push('\\'),
+                b'/' => out.push('/'),
+                b'b' => out.push('\u{8}'),
+                b'f' => out.push('\u{c}'),
+                b'n' => out.push('\n'),
+                b'r' => out.push('\r'),
+                b't' => out.push('\t'),
+                b'u' => {
+                    let mut c = self.hex()?;
+                    if (0xd800..0xdc00).contains(&c) && self.eat("\\u") {
+                        let low = self.hex()?;
+                        c = 0x10000 + ((c - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                    }
+                    out.push(char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                _ => {
+                    self.i -= 1;
+                    return Err(self.error("invalid escape"));
+                }
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, ParseError> {
+        let digits = self
+            .s
+            .get(self.i..self.i + 4)
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let c =
+            u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.i += 4;
+        Ok(c)
+    }
+}
+
+/// A diagnostic read from `rustc`'s JSON output.
+pub struct Message {
+    pub diagnostic: Diagnostic,
+
+    /// How `rustc` rendered the diagnostic, if it said.
+    pub rendered: Option<String>,
+}
+
+/// Convert a `rustc` JSON diagnostic, or a cargo `compiler-message` record, to
+/// a [`Message`].
+///
+/// Returns `None` for anything else, such as cargo's `compiler-artifact`
+/// records, or diagnostics that aren't errors or warnings (e.g. the `failure-note`
+/// pointing at `--explain`).
+pub fn message(value: &Value) -> Option<Message> {
+    let value = match value.get("reason").and_then(Value::as_str) {
+        Some("compiler-message") => value.get("message")?,
+        Some(_) => return None,
+        None => value,
+    };
+
+    let str_field = |v: &Value, key| v.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+    let message = value.get("message")?.as_str()?.to_string();
+    let level = value.get("level")?.as_str()?;
+    let is_error = match level {
+        "error" | "error: internal compiler error" => true,
+        "warning" => false,
+        _ => return None,
+    };
+
+    let spans = value.get("spans").and_then(Value::as_array).unwrap_or(&[]);
+    let primary = spans
+        .iter()
+        .find(|s| s.get("is_primary").and_then(Value::as_bool) == Some(true))
+        .or(spans.first());
+
+    let file = match primary {
+        Some(p) => File {
+            path: str_field(p, "file_name"),
+            line: p.get("line_start").and_then(Value::as_usize).unwrap_or(0),
+            col: p.get("column_start").and_then(Value::as_usize).unwrap_or(0),
+        },
+        None => File {
+            path: String::new(),
+            line: 0,
+            col: 0,
+        },
+    };
+
+    let (symbol, color) = if is_error { ('^', 9) } else { ('^', 3) };
+    let mut code = Code(Vec::new());
+    for span in spans
+        .iter()
+        .filter(|s| str_field(s, "file_name") == file.path)
+    {
+        let primary = span.get("is_primary").and_then(Value::as_bool) == Some(true);
+        let label = span
+            .get("label")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let (symbol, color) = if primary { (symbol, color) } else { ('-', 12) };
+        add_span(&mut code, span, |range| Marker {
+            range,
+            symbol,
+            color,
+            message: label.clone(),
+            color_span: false,
+        });
+    }
+
+    let children = children_of(value);
+
+    let diagnostic = if is_error {
+        let error_code = value
+            .get("code")
+            .and_then(|c| c.get("code"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+
+        Diagnostic::Error(Error {
+            error_code,
+            message,
+            file,
+            code,
+            children,
+        })
+    } else {
+        Diagnostic::Warning(Warning {
+            message,
+            file,
+            code,
+            children,
+        })
+    };
+
+    Some(Message {
+        diagnostic,
+        rendered: value
+            .get("rendered")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+/// The children of a JSON diagnostic, and theirs. Anything that isn't a help
+/// or warning is a note.
+fn children_of(value: &Value) -> Vec<Child> {
+    let children = value.get("children").and_then(Value::as_array);
+    children
+        .unwrap_or(&[])
+        .iter()
+        .map(|child| {
+            let message = child.get("message").and_then(Value::as_str).unwrap_or("");
+            let kind = match child.get("level").and_then(Value::as_str) {
+                Some("help") => {
+                    let spans = child.get("spans").and_then(Value::as_array).unwrap_or(&[]);
+                    let applicability = spans
+                        .iter()
+                        .find_map(|s| s.get("suggestion_applicability")?.as_str())
+                        .and_then(Applicability::parse)
+                        .unwrap_or_default();
+                    ChildKind::Help(Help {
+                        message: message.to_string(),
+                        suggestion: suggestion(spans),
+                        style: SuggestionStyle::Verbose,
+                        applicability,
+                    })
+                }
+                Some("warning") => ChildKind::Warning(Note(message.to_string())),
+                _ => ChildKind::Note(Note(message.to_string())),
+            };
+
+            Child {
+                kind,
+                children: children_of(child),
+            }
+        })
+        .collect()
+}
+
+/// Add the lines of a span to `code`, marking the first.
+///
+/// If a line is already present, its marker is filled in if it had none;
+/// otherwise, another copy of the line is added for the new marker.
+fn add_span(
+    code: &mut Code,
+    span: &Value,
+    marker: impl Fn(std::ops::RangeInclusive<usize>) -> Marker,
+) {
+    let Some(start) = span.get("line_start").and_then(Value::as_usize) else {
+        return;
+    };
+
+    let text = span.get("text").and_then(Value::as_array).unwrap_or(&[]);
+    for (i, t) in text.iter().enumerate() {
+        let line_number = start + i;
+        let line = t.get("text").and_then(Value::as_str).unwrap_or("");
+        let marker = (i == 0).then(|| {
+            let len = line.chars().count();
+            let hs = t
+                .get("highlight_start")
+                .and_then(Value::as_usize)
+                .unwrap_or(1);
+            let he = if text.len() == 1 {
+                t.get("highlight_end")
+                    .and_then(Value::as_usize)
+                    .unwrap_or(hs + 1)
+            } else {
+                len + 1
+            };
+            marker(hs.saturating_sub(1)..=he.saturating_sub(2).max(hs.saturating_sub(1)))
+        });
+
+        insert_line(code, line_number, line, marker);
+    }
+}
+
+fn insert_line(code: &mut Code, line_number: usize, text: &str, marker: Option<Marker>) {
+    match code.binary_search_by_key(&line_number, |l| l.line_number) {
+        Ok(i) if code[i].marker.is_none() => code[i].marker = marker,
+        Ok(_) if marker.is_none() => {}
+
+        // several markers on one line are displayed together
+        _ => {
+            let i = code.partition_point(|l| l.line_number <= line_number);
+            code.insert(
+                i,
+                Line {
+                    code: text.to_string(),
+                    line_number,
+                    marker,
+                },
+            );
+        }
+    }
+}
+
+/// The start and end columns (starting at 0) of a replacement, and its text.
+type Replacement = (usize, usize, String);
+
+/// Build the suggested code for a `help` child's spans.
+///
+/// Replacements on the same line are applied together. A suggestion with a
+/// replacement spanning several lines is left out entirely, since suggested
+/// [`Line`]s can only change lines, not join them.
+fn suggestion(spans: &[Value]) -> Option<Code> {
+    let mut lines: Vec<(usize, String, Vec<Replacement>)> = Vec::new();
+    for span in spans {
+        let Some(replacement) = span.get("suggested_replacement").and_then(Value::as_str) else {
+            continue;
+        };
+
+        let Some(line_number) = span.get("line_start").and_then(Value::as_usize) else {
+            continue;
+        };
+
+        let line_end = span.get("line_end").and_then(Value::as_usize);
+        if line_end.is_some_and(|end| end != line_number) {
+            return None;
+        }
+
+        let line = span
+            .get("text")
+            .and_then(Value::as_array)
+            .and_then(|t| t.first())
+            .and_then(|t| t.get("text"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+            .to_string();
+        let col = |key| {
+            span.get(key)
+                .and_then(Value::as_usize)
+                .unwrap_or(1)
+                .saturating_sub(1)
+        };
+        let edit = (
+            col("column_start"),
+            col("column_end"),
+            replacement.to_string(),
+        );
+        match lines.iter_mut().find(|(l, _, _)| *l == line_number) {
+            Some((_, _, edits)) => edits.push(edit),
+            None => lines.push((line_number, line, vec![edit])),
+        }
+    }
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    lines.sort_by_key(|(l, _, _)| *l);
+    let code = lines
+        .into_iter()
+        .map(|(line_number, line, mut edits)| {
+            edits.sort_by_key(|(start, _, _)| *start);
+
+            let chars: Vec<char> = line.chars().collect();
+            let mut new = String::new();
+            let mut last = 0;
+            let mut marked: Option<(usize, usize)> = None;
+            let mut insertion = true;
+            for (start, end, replacement) in edits {
+                let start = start.clamp(last, chars.len());
+                let end = end.clamp(start, chars.len());
+                new.extend(&chars[last..start]);
+
+                let from = new.chars().count();
+                new.push_str(&replacement);
+                let to = new.chars().count();
+                insertion &= start == end;
+                if to > from {
+                    marked = Some((marked.map_or(from, |(s, _)| s), to - 1));
+                }
+                last = end;
+            }
+            new.extend(&chars[last..]);
+
+            let marker = marked.map(|(start, end)| Marker {
+                range: start..=end,
+                symbol: if insertion { '+' } else { '~' },
+                color: 10,
+                message: None,
+                color_span: true,
+            });
+
+            Line {
+                code: new,
+                line_number,
+                marker,
+            }
+        })
+        .collect();
+
+    Some(Code(code))
+}
+
+/// Why a [`Stream`] couldn't read a message.
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+
+    /// A line wasn't valid JSON.
+    Parse {
+        /// The line number, starting at 1.
+        line: usize,
+
+        /// The contents of the line.
+        text: String,
+
+        error: ParseError,
+    },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Parse { line, error, .. } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Reads [`Message`]s from `rustc`'s or cargo's JSON output, one per line.
+///
+/// Lines that don't start with `{` (such as cargo's progress output, if
+/// stderr was redirected) are skipped, as are records [`message`] ignores.
+pub struct Stream<R> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> Stream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Stream<R> {
+    type Item = Result<Message, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(e) => return Some(Err(StreamError::Io(e))),
+            };
+            self.line += 1;
+
+            if !text.trim_start().starts_with('{') {
+                continue;
+            }
+
+            match parse(&text) {
+                Ok(value) => {
+                    if let Some(message) = message(&value) {
+                        return Some(Ok(message));
+                    }
+                }
+
+                Err(error) => {
+                    return Some(Err(StreamError::Parse {
+                        line: self.line,
+                        text,
+                        error,
+                    }))
+                }
+            }
+        }
+    }
+}
+
+/// A difference between frack's rendering of a diagnostic and `rustc`'s.
+pub struct Mismatch {
+    /// The line number in the rendered output, starting at 1.
+    pub line: usize,
+
+    /// The line as `rustc` rendered it, if it did.
+    pub expected: Option<String>,
+
+    /// The line as frack rendered it, if it did.
+    pub found: Option<String>,
+}
+
+/// Compare frack's rendering of a diagnostic to `rustc`'s, ignoring trailing
+/// whitespace. Returns the first difference, if any.
+pub fn fidelity(diag: &Diagnostic, rendered: &str) -> Option<Mismatch> {
+    let ours = Plain(diag).to_string();
+    let mut expected = rendered.trim_end().lines().map(str::trim_end);
+    let mut found = ours.trim_end().lines().map(str::trim_end);
+
+    let mut line = 0;
+    loop {
+        line += 1;
+        match (expected.next(), found.next()) {
+            (None, None) => return None,
+            (e, f) if e != f => {
+                return Some(Mismatch {
+                    line,
+                    expected: e.map(str::to_string),
+                    found: f.map(str::to_string),
+                })
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{message, parse};
+    use crate::{fix, Diagnostic};
+
+    const SOURCE: &str = "fn main() {\n    let x = bar(1,\n        1,\n    );\n}\n";
+
+    /// A `rustc` error about line 2 of [`SOURCE`], with a help suggesting
+    /// `replacement` for the given span.
+    fn diagnostic(span: (usize, usize, usize, usize), replacement: &str) -> Diagnostic {
+        let (line_start, column_start, line_end, column_end) = span;
+        let text: Vec<_> = SOURCE.lines().collect::<Vec<_>>()[line_start - 1..line_end]
+            .iter()
+            .map(|t| format!(r#"{{"text": "{t}", "highlight_start": 1, "highlight_end": 1}}"#))
+            .collect();
+        let json = format!(
+            r#"{{
+                "$message_type": "diagnostic",
+                "message": "this function takes 1 argument but 2 arguments were supplied",
+                "code": {{"code": "E0061", "explanation": null}},
+                "level": "error",
+                "spans": [{{
+                    "file_name": "src/main.rs",
+                    "line_start": 2, "line_end": 2, "column_start": 13, "column_end": 16,
+                    "is_primary": true,
+                    "text": [{{"text": "    let x = bar(1,", "highlight_start": 13, "highlight_end": 16}}],
+                    "label": null, "suggested_replacement": null, "suggestion_applicability": null
+                }}],
+                "children": [{{
+                    "message": "remove the extra argument",
+                    "code": null,
+                    "level": "help",
+                    "spans": [{{
+                        "file_name": "src/main.rs",
+                        "line_start": {line_start}, "line_end": {line_end},
+                        "column_start": {column_start}, "column_end": {column_end},
+                        "is_primary": true,
+                        "text": [{}],
+                        "label": null,
+                        "suggested_replacement": "{replacement}",
+                        "suggestion_applicability": "MachineApplicable"
+                    }}],
+                    "children": [],
+                    "rendered": null
+                }}],
+                "rendered": null
+            }}"#,
+            text.join(", "),
+        );
+
+        message(&parse(&json).unwrap()).unwrap().diagnostic
+    }
+
+    fn fixed(diagnostic: Diagnostic) -> String {
+        let fixes = fix::fixes(&[diagnostic]);
+        fix::apply(SOURCE, &fixes).source
+    }
+
+    #[test]
+    fn single_line_suggestions_apply() {
+        let diagnostic = diagnostic((2, 13, 2, 16), "baz");
+        assert!(diagnostic.helps().all(|h| h.suggestion.is_some()));
+        assert_eq!(
+            fixed(diagnostic),
+            "fn main() {\n    let x = baz(1,\n        1,\n    );\n}\n",
+        );
+    }
+
+    #[test]
+    fn multi_line_suggestions_are_left_out() {
+        let diagnostic = diagnostic((2, 18, 4, 6), ")");
+        assert_eq!(diagnostic.helps().count(), 1);
+        assert!(diagnostic.helps().all(|h| h.suggestion.is_none()));
+        assert_eq!(fixed(diagnostic), SOURCE);
+    }
+}
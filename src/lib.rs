@@ -1,19 +1,45 @@
 //! Utilities for creating `rustc`-like error messages, for fun or for actual use.
 //!
 //! Note that all implementations of [`Display`](fmt::Display), as well as
-//! methods named `display`, utilize ANSI escape sequences. There's currently no
-//! way to change this.
+//! methods named `display`, utilize ANSI escape sequences. To strip them, wrap
+//! the value in [`Plain`].
 
 use std::fmt;
-use std::ops::{Deref, DerefMut, RangeInclusive};
-
+use std::ops::{Deref, DerefMut, Range, RangeInclusive};
+
+use highlight::Highlight;
+
+pub mod args;
+pub mod ci;
+pub mod codes;
+mod edit;
+pub mod fix;
+pub mod frackfile;
+pub mod highlight;
+pub mod index;
+pub mod json;
+pub mod locate;
+pub mod lsp;
+pub mod markdown;
+pub mod patch;
+pub mod regex;
+pub mod sarif;
+pub mod short;
+pub mod similar;
+pub mod style;
+pub mod template;
 mod util;
+pub mod xml;
 
 /// An error in `rustc` style.
 ///
 /// To display using ANSI escape codes, use the [`Display`](fmt::Display) impl.
+/// To end it with a footer pointing at `--explain`, display it
+/// [explained](codes::Registry::explained) by a registry of codes.
 pub struct Error {
     /// The `E0502` in `error[E0502]: ...`.
+    ///
+    /// If empty, displays as just `error: ...`.
     pub error_code: String,
 
     /// The message to display after `error[...]: `.
@@ -25,44 +51,44 @@ pub struct Error {
     /// The code the error is about.
     pub code: Code,
 
-    /// Any number of help messages.
-    ///
-    /// These get displayed before the notes.
-    pub helps: Vec<Help>,
-
-    /// Any number of notes.
-    ///
-    /// These get displayed after the help messages.
-    pub notes: Vec<Note>,
+    /// Any number of notes, helps, and suggestions, displayed in order.
+    pub children: Vec<Child>,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with(None, f)
+    }
+}
+
+impl Error {
+    /// Write out like the [`Display`](fmt::Display) impl, highlighting code
+    /// with `highlight` if given.
+    pub fn display_with(
+        &self,
+        highlight: Option<&dyn Highlight>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
         bold(f)?;
         color(9, f)?;
-        write!(f, "error[{}]", self.error_code)?;
+        if self.error_code.is_empty() {
+            write!(f, "error")?;
+        } else {
+            write!(f, "error[{}]", self.error_code)?;
+        }
         normal(f)?;
 
         bold(f)?;
         writeln!(f, ": {}", self.message)?;
 
-        color(12, f)?;
-        write!(f, " --> ")?;
-        normal(f)?;
-        writeln!(f, "{}:{}:{}", self.file.path, self.file.line, self.file.col)?;
-
-        let last = self.helps.is_empty() && self.notes.is_empty();
-        self.code.display(!last, f)?;
+        self.file.display(self.code.line_number_width(), f)?;
 
-        for help in &self.helps {
-            help.display(false, f)?;
+        if !self.code.is_empty() {
+            self.code
+                .display_with(!self.children.is_empty(), highlight, f)?;
         }
 
-        for note in &self.notes {
-            write!(f, "{note}")?;
-        }
-
-        Ok(())
+        display_children(&self.children, None, 0, highlight, f)
     }
 }
 
@@ -79,19 +105,24 @@ pub struct Warning {
     /// The code the warning is about.
     pub code: Code,
 
-    /// Any number of help messages.
-    ///
-    /// These get displayed before the notes.
-    pub helps: Vec<Help>,
-
-    /// Any number of notes.
-    ///
-    /// These get displayed after the help messages.
-    pub notes: Vec<Note>,
+    /// Any number of notes, helps, and suggestions, displayed in order.
+    pub children: Vec<Child>,
 }
 
 impl fmt::Display for Warning {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with(None, f)
+    }
+}
+
+impl Warning {
+    /// Write out like the [`Display`](fmt::Display) impl, highlighting code
+    /// with `highlight` if given.
+    pub fn display_with(
+        &self,
+        highlight: Option<&dyn Highlight>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
         bold(f)?;
         color(3, f)?;
         write!(f, "warning")?;
@@ -100,39 +131,216 @@ impl fmt::Display for Warning {
         bold(f)?;
         writeln!(f, ": {}", self.message)?;
 
-        color(12, f)?;
-        write!(f, " --> ")?;
-        normal(f)?;
-        writeln!(f, "{}:{}:{}", self.file.path, self.file.line, self.file.col)?;
+        self.file.display(self.code.line_number_width(), f)?;
+
+        if !self.code.is_empty() {
+            self.code
+                .display_with(!self.children.is_empty(), highlight, f)?;
+        }
+
+        let gutter = self.code.line_number_width() + 3;
+        display_children(&self.children, Some(gutter), 0, highlight, f)
+    }
+}
+
+/// Either an [`Error`] or a [`Warning`].
+///
+/// Used by the output formats that handle both, e.g. [`json`].
+pub enum Diagnostic {
+    Error(Error),
+    Warning(Warning),
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
 
-        let last = self.helps.is_empty() && self.notes.is_empty();
-        self.code.display(!last, f)?;
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
 
-        for help in &self.helps {
-            help.display(false, f)?;
+impl Diagnostic {
+    pub fn level(&self) -> Level {
+        match self {
+            Self::Error(_) => Level::Error,
+            Self::Warning(_) => Level::Warning,
         }
+    }
 
-        for note in &self.notes {
-            bold(f)?;
-            color(12, f)?;
-            write!(
-                f,
-                "{: >width$}",
-                " = ",
-                width = self.code.line_number_width() + 3
-            )?;
-            normal(f)?;
+    /// The error code, if this is an [`Error`] and it has one.
+    pub fn error_code(&self) -> Option<&str> {
+        match self {
+            Self::Error(e) if !e.error_code.is_empty() => Some(&e.error_code),
+            _ => None,
+        }
+    }
 
-            write!(f, "{note}")?;
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Error(e) => &e.message,
+            Self::Warning(w) => &w.message,
         }
+    }
 
-        Ok(())
+    pub fn file(&self) -> &File {
+        match self {
+            Self::Error(e) => &e.file,
+            Self::Warning(w) => &w.file,
+        }
+    }
+
+    pub fn code(&self) -> &Code {
+        match self {
+            Self::Error(e) => &e.code,
+            Self::Warning(w) => &w.code,
+        }
+    }
+
+    pub fn children(&self) -> &[Child] {
+        match self {
+            Self::Error(e) => &e.children,
+            Self::Warning(w) => &w.children,
+        }
+    }
+
+    /// Every help, including nested ones, in the order they're displayed.
+    pub fn helps(&self) -> impl Iterator<Item = &Help> {
+        descendants(self.children()).filter_map(|(_, c)| match &c.kind {
+            ChildKind::Help(h) => Some(h),
+            _ => None,
+        })
+    }
+
+    /// Every note, including nested ones, in the order they're displayed.
+    pub fn notes(&self) -> impl Iterator<Item = &Note> {
+        descendants(self.children()).filter_map(|(_, c)| match &c.kind {
+            ChildKind::Note(n) => Some(n),
+            _ => None,
+        })
+    }
+
+    /// The line the diagnostic is primarily about.
+    ///
+    /// This is the marked line at [`File::line`], preferring a `^` marker; or
+    /// failing that, the first line marked with `^`; or the first marked line.
+    pub fn primary(&self) -> Option<&Line> {
+        let marked = || self.code().iter().filter(|l| l.marker.is_some());
+        let caret = |l: &&Line| l.marker.as_ref().is_some_and(|m| m.symbol == '^');
+        let here = |l: &&Line| l.line_number == self.file().line;
+
+        marked()
+            .filter(here)
+            .find(caret)
+            .or_else(|| marked().find(here))
+            .or_else(|| marked().find(caret))
+            .or_else(|| marked().next())
+    }
+
+    /// The message of the [primary](Diagnostic::primary) line's marker.
+    pub fn label(&self) -> Option<&str> {
+        self.primary()?.marker.as_ref()?.message.as_deref()
+    }
+
+    /// The message, followed by the [label](Diagnostic::label) if there is one,
+    /// for formats with room for only one message.
+    pub(crate) fn labeled_message(&self) -> String {
+        match self.label() {
+            Some(label) => format!("{}: {label}", self.message()),
+            None => self.message().to_string(),
+        }
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(e: Error) -> Self {
+        Self::Error(e)
+    }
+}
+
+impl From<Warning> for Diagnostic {
+    fn from(w: Warning) -> Self {
+        Self::Warning(w)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error(e) => e.fmt(f),
+            Self::Warning(w) => w.fmt(f),
+        }
+    }
+}
+
+/// Displays the inner value without ANSI escape codes.
+///
+/// ```rust
+/// let warning = frack::warning! {
+///     "this looks silly";
+///     "src/main.rs", 3, 5;
+///     "    foo();";
+///     4..=8;
+/// };
+///
+/// let plain = frack::Plain(&warning).to_string();
+/// assert!(plain.starts_with("warning: this looks silly\n"));
+/// ```
+pub struct Plain<T>(pub T);
+
+impl<T: fmt::Display> fmt::Display for Plain<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use fmt::Write;
+
+        struct Strip<'a, 'b> {
+            f: &'a mut fmt::Formatter<'b>,
+            escape: bool,
+        }
+
+        impl fmt::Write for Strip<'_, '_> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let mut rest = s;
+                while !rest.is_empty() {
+                    if self.escape {
+                        match rest.find(|c: char| c.is_ascii_alphabetic()) {
+                            Some(i) => {
+                                self.escape = false;
+                                rest = &rest[i + 1..];
+                            }
+                            None => return Ok(()),
+                        }
+                    } else {
+                        match rest.find('\x1b') {
+                            Some(i) => {
+                                self.f.write_str(&rest[..i])?;
+                                self.escape = true;
+                                rest = &rest[i + 1..];
+                            }
+                            None => return self.f.write_str(rest),
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+
+        write!(Strip { f, escape: false }, "{}", self.0)
     }
 }
 
 /// A code block for a [`Help`], [`Warning`], or [`Error`].
 ///
 /// If two subesquent [`Line`]s of code aren't adjacent, prints ellipses between them.
+/// Subsequent lines with the same line number and code are shown once, with all
+/// of their markers.
 pub struct Code(pub Vec<Line>);
 
 impl Deref for Code {
@@ -165,6 +373,67 @@ impl Code {
         }])
     }
 
+    /// A suggested revision of the line `original`, with markers under what
+    /// changed in `suggested`: `~` under replacements, and `+` under
+    /// insertions.
+    ///
+    /// The changes are found with a character-level diff. Each one gets its own
+    /// marker, as a [`Line`] with the same line number. If text was only
+    /// removed, `original` is shown instead, with `-` under what was removed.
+    ///
+    /// ```rust
+    /// let code = frack::Code::suggestion("let Foo { x } = z;", "let Foo { x, y } = z;", 8);
+    /// let marker = code[0].marker.as_ref().unwrap();
+    /// assert_eq!((marker.range.clone(), marker.symbol), (11..=13, '+'));
+    ///
+    /// let code = frack::Code::suggestion("let abc = 1;", "let abd = 1;", 8);
+    /// assert_eq!(code[0].marker.as_ref().unwrap().range, 6..=6);
+    ///
+    /// // separate edits get separate markers
+    /// let code = frack::Code::suggestion("let x = 1;", "let mut x = 1u8;", 8);
+    /// let ranges: Vec<_> = code.iter().map(|l| l.marker.as_ref().unwrap().range.clone()).collect();
+    /// assert_eq!(ranges, [4..=7, 13..=14]);
+    /// ```
+    pub fn suggestion(original: &str, suggested: &str, line_number: usize) -> Self {
+        let changes = edit::changes(original, suggested);
+        let removed = changes.iter().all(|(_, new)| new.is_empty());
+
+        let (code, color) = if removed {
+            (original, 9)
+        } else {
+            (suggested, 10)
+        };
+
+        let lines: Vec<Line> = changes
+            .into_iter()
+            .filter_map(|(old, new)| {
+                let (range, symbol) = match (removed, old.is_empty()) {
+                    (true, _) => (old, '-'),
+                    (false, true) => (new, '+'),
+                    (false, false) => (new, '~'),
+                };
+
+                (!range.is_empty()).then(|| Line {
+                    code: code.to_string(),
+                    line_number,
+                    marker: Some(Marker {
+                        range: range.start..=range.end - 1,
+                        symbol,
+                        color,
+                        message: None,
+                        color_span: true,
+                    }),
+                })
+            })
+            .collect();
+
+        if lines.is_empty() {
+            return Self::single(suggested, line_number, None);
+        }
+
+        Self(lines)
+    }
+
     /// Returns the maximum width of the line numbers of this code block.
     pub fn line_number_width(&self) -> usize {
         self.0
@@ -184,9 +453,20 @@ impl Code {
     ///
     /// If `extend == false` and this code has a [marker](Marker), prints out an
     /// extra line; to mimic `rustc`, `extend` should be true if it's the main
-    /// code block for the warning/error and there are no helps/notes, or the
+    /// code block for the warning/error and there are no children, or the
     /// code block has no marker.
     pub fn display(&self, extend: bool, f: &mut fmt::Formatter) -> fmt::Result {
+        self.display_with(extend, None, f)
+    }
+
+    /// Like [`display`](Code::display), highlighting the code with
+    /// `highlight` if given.
+    pub fn display_with(
+        &self,
+        extend: bool,
+        highlight: Option<&dyn Highlight>,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
         let lno_width = self.line_number_width();
 
         bold(f)?;
@@ -195,7 +475,9 @@ impl Code {
         normal(f)?;
 
         let mut last = None;
-        for line in &self.0 {
+        let same = |a: &Line, b: &Line| a.line_number == b.line_number && a.code == b.code;
+        for lines in self.0.chunk_by(same) {
+            let line = &lines[0];
             if last.is_some_and(|l| line.line_number - 1 != l) {
                 bold(f)?;
                 color(12, f)?;
@@ -204,7 +486,10 @@ impl Code {
             }
             last = Some(line.line_number);
 
-            line.display(lno_width, f)?;
+            match lines {
+                [line] => line.display_with(lno_width, highlight, f)?,
+                _ => display_markers(lines, lno_width, highlight, f)?,
+            }
         }
 
         if extend || !self.end_marker() {
@@ -218,6 +503,118 @@ impl Code {
     }
 }
 
+/// Write out one line of code with the markers of all of `lines`, like `rustc`:
+///
+/// ```text
+/// 3 |     let x = foo(a, b);
+///   |             ---   ^ second
+///   |             |
+///   |             first
+/// ```
+///
+/// Markers that overlap an earlier one are left out.
+fn display_markers(
+    lines: &[Line],
+    lno_width: usize,
+    highlight: Option<&dyn Highlight>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    let mut markers: Vec<&Marker> = lines.iter().filter_map(|l| l.marker.as_ref()).collect();
+    markers.sort_by_key(|m| *m.range.start());
+    let mut end = 0;
+    markers.retain(|m| {
+        let keep = *m.range.start() >= end;
+        end = end.max(m.range.end() + 1);
+        keep
+    });
+
+    let gutter = |f: &mut fmt::Formatter<'_>| {
+        bold(f)?;
+        color(12, f)?;
+        write!(f, "{: >width$}", " | ", width = lno_width + 3)?;
+        normal(f)
+    };
+
+    let code = &lines[0].code;
+    bold(f)?;
+    color(12, f)?;
+    write!(f, "{: <width$} | ", lines[0].line_number, width = lno_width)?;
+    normal(f)?;
+
+    let mut colors = highlight.map_or_else(Vec::new, |h| highlights(h, code));
+    colors.extend(
+        markers
+            .iter()
+            .filter(|m| m.color_span)
+            .map(|m| (edit::bytes(code, &m.range), m.color)),
+    );
+    write_colored(code, &colors, f)?;
+    writeln!(f)?;
+
+    let Some((last, labeled)) = markers.split_last() else {
+        return Ok(());
+    };
+    let labeled: Vec<&&Marker> = labeled.iter().filter(|m| m.label().is_some()).collect();
+
+    gutter(f)?;
+    let mut column = 0;
+    for m in &markers {
+        let len = m.range.end() - m.range.start() + 1;
+        bold(f)?;
+        color(m.color, f)?;
+        write!(
+            f,
+            "{: >pad$}{}",
+            "",
+            Repeat(len, m.symbol),
+            pad = m.range.start() - column
+        )?;
+        normal(f)?;
+        column = m.range.end() + 1;
+    }
+    if let Some(message) = last.label() {
+        bold(f)?;
+        color(last.color, f)?;
+        write!(f, " {message}")?;
+        normal(f)?;
+    }
+    writeln!(f)?;
+
+    if labeled.is_empty() {
+        return Ok(());
+    }
+
+    // earlier labels go below, connected to their markers by `|`s
+    let connectors = |f: &mut fmt::Formatter<'_>, labeled: &[&&Marker]| {
+        gutter(f)?;
+        let mut column = 0;
+        for m in labeled {
+            bold(f)?;
+            color(m.color, f)?;
+            write!(f, "{: >pad$}|", "", pad = m.range.start() - column)?;
+            normal(f)?;
+            column = m.range.start() + 1;
+        }
+        Ok(column)
+    };
+
+    connectors(f, &labeled)?;
+    writeln!(f)?;
+
+    for i in (0..labeled.len()).rev() {
+        let column = connectors(f, &labeled[..i])?;
+        let m = labeled[i];
+        bold(f)?;
+        color(m.color, f)?;
+        let message = m.label().unwrap_or_default();
+        write!(f, "{: >pad$}{message}", "", pad = m.range.start() - column)?;
+        normal(f)?;
+        writeln!(f)?;
+    }
+
+    Ok(())
+}
+
 /// A single line in a [`Code`] block.
 pub struct Line {
     /// A single line of code.
@@ -236,44 +633,36 @@ impl Line {
     /// Write out with ANSI escape codes. Behaves like an impl for
     /// [`Display`](fmt::Display).
     pub fn display(&self, lno_width: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut line = || {
-            bold(f)?;
-            color(12, f)?;
-            write!(f, "{: <width$} | ", self.line_number, width = lno_width)?;
-            normal(f)?;
-            writeln!(f, "{}", self.code)?;
-
-            Ok(())
-        };
-
-        if let Some(m) = &self.marker {
-            if m.color_span && *m.range.end() <= self.code.len() {
-                bold(f)?;
-                color(12, f)?;
-                write!(f, "{: <width$} | ", self.line_number, width = lno_width)?;
-                normal(f)?;
-
-                let (start, rest) = self.code.split_at(*m.range.start());
-                let (mid, end) = rest.split_at(m.range.end().saturating_sub(*m.range.start()) + 1);
-
-                write!(f, "{start}")?;
+        self.display_with(lno_width, None, f)
+    }
 
-                color(m.color, f)?;
-                write!(f, "{mid}")?;
+    /// Like [`display`](Line::display), highlighting the code with
+    /// `highlight` if given.
+    pub fn display_with(
+        &self,
+        lno_width: usize,
+        highlight: Option<&dyn Highlight>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        bold(f)?;
+        color(12, f)?;
+        write!(f, "{: <width$} | ", self.line_number, width = lno_width)?;
+        normal(f)?;
 
-                normal(f)?;
-                writeln!(f, "{end}")?;
-            } else {
-                line()?;
-            }
+        // the marker's color goes over the highlighting
+        let mut colors = highlight.map_or_else(Vec::new, |h| highlights(h, &self.code));
+        if let Some(m) = self.marker.as_ref().filter(|m| m.color_span) {
+            colors.push((edit::bytes(&self.code, &m.range), m.color));
+        }
+        write_colored(&self.code, &colors, f)?;
+        writeln!(f)?;
 
+        if let Some(m) = &self.marker {
             bold(f)?;
             color(12, f)?;
             write!(f, "{: >width$}", " | ", width = lno_width + 3)?;
             normal(f)?;
             write!(f, "{m}")?;
-        } else {
-            line()?;
         }
 
         Ok(())
@@ -286,29 +675,145 @@ pub struct Help {
 
     /// A suggested revision.
     pub suggestion: Option<Code>,
+
+    /// How to show the suggestion.
+    pub style: SuggestionStyle,
+
+    /// How confident the suggestion is, for tools that apply it.
+    pub applicability: Applicability,
 }
 
 impl Help {
+    /// The suggested text to show in the message, if the suggestion is shown
+    /// [inline](SuggestionStyle::Inline).
+    ///
+    /// This is the text marked in the suggestion's only line, e.g. `_x` in
+    /// `help: if this is intentional, prefix it with an underscore: `_x``.
+    pub fn inline(&self) -> Option<&str> {
+        let [line] = &self.suggestion.as_ref()?[..] else {
+            return None;
+        };
+        let marker = line
+            .marker
+            .as_ref()
+            .filter(|m| m.range.start() <= m.range.end())?;
+
+        let snippet = line.code[edit::marked(line, &marker.range)].trim();
+
+        match self.style {
+            SuggestionStyle::Inline => Some(snippet),
+            SuggestionStyle::Auto
+                if !snippet.is_empty()
+                    && marker.symbol != '-'
+                    && marker.message.is_none()
+                    && self.message.split_whitespace().count() < 10 =>
+            {
+                Some(snippet)
+            }
+            _ => None,
+        }
+    }
+
     /// Write out with ANSI escape codes. Behaves like an impl for
     /// [`Display`](fmt::Display).
     ///
     /// If `extend == false` and this code has a [marker](Marker), prints out an
     /// extra line; to mimic `rustc`, `extend` should be true if it's the main
-    /// code block for the warning/error and there are no helps/notes, or the
+    /// code block for the warning/error and there are no children, or the
     /// code block has no marker.
     pub fn display(&self, extend: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.display_with(extend, None, f)
+    }
+
+    /// Like [`display`](Help::display), highlighting the suggestion with
+    /// `highlight` if given.
+    pub fn display_with(
+        &self,
+        extend: bool,
+        highlight: Option<&dyn Highlight>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
         bold(f)?;
         color(14, f)?;
         write!(f, "help")?;
         normal(f)?;
 
+        if let Some(snippet) = self.inline() {
+            return writeln!(f, ": {}: `{snippet}`", self.message);
+        }
+
         writeln!(f, ": {}", self.message)?;
 
-        if let Some(s) = &self.suggestion {
-            s.display(extend, f)?;
+        match &self.suggestion {
+            Some(s) if self.style != SuggestionStyle::Hidden => {
+                s.display_with(extend, highlight, f)
+            }
+            _ => Ok(()),
         }
+    }
+}
 
-        Ok(())
+/// How a [`Help`] shows its suggestion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionStyle {
+    /// Choose like `rustc` does: inline if the suggestion is one line with an
+    /// unlabeled [marker](Marker) and the message is short, verbose otherwise.
+    Auto,
+
+    /// Show the marked text in the message, e.g. `help: try: `x.clone()``.
+    /// Falls back to verbose if the suggestion isn't a single marked line.
+    Inline,
+
+    /// Show only the message.
+    Hidden,
+
+    /// Show the suggestion as a code block.
+    #[default]
+    Verbose,
+}
+
+/// How confident a [`Help`]'s suggestion is, following `rustc` and `rustfix`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    /// The suggestion is definitely what the user intended, and can be
+    /// applied automatically.
+    MachineApplicable,
+
+    /// The suggestion may be what the user intended, but it's uncertain. It
+    /// should compile if applied, but may not do what's wanted.
+    MaybeIncorrect,
+
+    /// The suggestion contains placeholders like `(...)` or `{ /* fields */ }`,
+    /// so it won't compile as-is.
+    HasPlaceholders,
+
+    /// How applicable the suggestion is isn't known.
+    #[default]
+    Unspecified,
+}
+
+impl Applicability {
+    /// The applicability named like in `rustc`'s JSON, e.g.
+    /// `"MachineApplicable"`.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "MachineApplicable" => Some(Self::MachineApplicable),
+            "MaybeIncorrect" => Some(Self::MaybeIncorrect),
+            "HasPlaceholders" => Some(Self::HasPlaceholders),
+            "Unspecified" => Some(Self::Unspecified),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Applicability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MachineApplicable => write!(f, "MachineApplicable"),
+            Self::MaybeIncorrect => write!(f, "MaybeIncorrect"),
+            Self::HasPlaceholders => write!(f, "HasPlaceholders"),
+            Self::Unspecified => write!(f, "Unspecified"),
+        }
     }
 }
 
@@ -341,6 +846,141 @@ impl fmt::Display for Note {
     }
 }
 
+/// A note, help, or warning attached to a [`Warning`] or [`Error`], or to
+/// another child.
+///
+/// ```rust
+/// use frack::{Child, Note};
+///
+/// let child = Child::help("consider borrowing here")
+///     .child(Child::note("`x` is moved here"))
+///     .child(Note("see issue #1234".to_string()));
+/// assert_eq!(child.children.len(), 2);
+/// ```
+pub struct Child {
+    pub kind: ChildKind,
+
+    /// Children of this child, displayed indented underneath it.
+    pub children: Vec<Child>,
+}
+
+/// What a [`Child`] is.
+pub enum ChildKind {
+    Note(Note),
+
+    /// A help, which may carry a suggestion.
+    Help(Help),
+
+    /// A note displayed as `warning: ...`.
+    Warning(Note),
+}
+
+impl Child {
+    pub fn new(kind: ChildKind) -> Self {
+        Self {
+            kind,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn note(message: impl ToString) -> Self {
+        Self::new(ChildKind::Note(Note(message.to_string())))
+    }
+
+    /// A help without a suggestion.
+    pub fn help(message: impl ToString) -> Self {
+        Self::new(ChildKind::Help(Help {
+            message: message.to_string(),
+            suggestion: None,
+            style: SuggestionStyle::Verbose,
+            applicability: Applicability::Unspecified,
+        }))
+    }
+
+    pub fn warning(message: impl ToString) -> Self {
+        Self::new(ChildKind::Warning(Note(message.to_string())))
+    }
+
+    /// Add a nested child.
+    pub fn child(mut self, child: impl Into<Child>) -> Self {
+        self.children.push(child.into());
+        self
+    }
+}
+
+impl From<Help> for Child {
+    fn from(help: Help) -> Self {
+        Self::new(ChildKind::Help(help))
+    }
+}
+
+impl From<Note> for Child {
+    fn from(note: Note) -> Self {
+        Self::new(ChildKind::Note(note))
+    }
+}
+
+/// Write out `children` at `depth`, indented by two spaces per level.
+///
+/// Notes and warnings are prefixed with ` = `, right-aligned to `gutter`, if
+/// given, like in a [`Warning`].
+fn display_children(
+    children: &[Child],
+    gutter: Option<usize>,
+    depth: usize,
+    highlight: Option<&dyn Highlight>,
+    f: &mut fmt::Formatter<'_>,
+) -> fmt::Result {
+    for child in children {
+        let prefix = |f: &mut fmt::Formatter<'_>| -> fmt::Result {
+            if let Some(width) = gutter {
+                bold(f)?;
+                color(12, f)?;
+                write!(f, "{: >width$}", " = ")?;
+                normal(f)?;
+            }
+
+            write!(f, "{}", Repeat(depth * 2, ' '))
+        };
+
+        match &child.kind {
+            ChildKind::Help(help) => {
+                write!(f, "{}", Repeat(depth * 2, ' '))?;
+                help.display_with(false, highlight, f)?;
+            }
+            ChildKind::Note(note) => {
+                prefix(f)?;
+                write!(f, "{note}")?;
+            }
+            ChildKind::Warning(note) => {
+                prefix(f)?;
+                bold(f)?;
+                color(3, f)?;
+                write!(f, "warning")?;
+                normal(f)?;
+                writeln!(f, ": {}", note.0)?;
+            }
+        }
+
+        display_children(&child.children, gutter, depth + 1, highlight, f)?;
+    }
+
+    Ok(())
+}
+
+/// Every child in `children` and their descendants, depth-first, with how
+/// deeply they're nested.
+fn descendants(children: &[Child]) -> impl Iterator<Item = (usize, &Child)> {
+    let mut all = Vec::new();
+    let mut stack: Vec<_> = children.iter().rev().map(|c| (0, c)).collect();
+    while let Some((depth, child)) = stack.pop() {
+        all.push((depth, child));
+        stack.extend(child.children.iter().rev().map(|c| (depth + 1, c)));
+    }
+
+    all.into_iter()
+}
+
 /// An underline for a piece of [`Code`].
 pub struct Marker {
     /// The code the marker should underline.
@@ -367,6 +1007,13 @@ pub struct Marker {
     pub color_span: bool,
 }
 
+impl Marker {
+    /// The message, unless it's empty, which displays like no message.
+    pub(crate) fn label(&self) -> Option<&str> {
+        self.message.as_deref().filter(|m| !m.is_empty())
+    }
+}
+
 impl fmt::Display for Marker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         bold(f)?;
@@ -378,7 +1025,7 @@ impl fmt::Display for Marker {
         );
         write!(f, "{: >start$}{mark}", "", start = self.range.start(),)?;
 
-        if let Some(m) = &self.message {
+        if let Some(m) = self.label() {
             write!(f, " {m}")?;
         }
 
@@ -388,12 +1035,32 @@ impl fmt::Display for Marker {
 }
 
 /// The path, line, and column of a piece of [`Code`].
+///
+/// If `path` is empty, the location isn't displayed.
 pub struct File {
     pub path: String,
     pub line: usize,
     pub col: usize,
 }
 
+impl File {
+    /// Write out the ` --> path:line:col` line with ANSI escape codes. Behaves
+    /// like an impl for [`Display`](fmt::Display).
+    ///
+    /// `lno_width` is the width of the line numbers of the accompanying code
+    /// block, used to align the arrow.
+    pub fn display(&self, lno_width: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            return Ok(());
+        }
+
+        color(12, f)?;
+        write!(f, "{: >lno_width$}--> ", "")?;
+        normal(f)?;
+        writeln!(f, "{}:{}:{}", self.path, self.line, self.col)
+    }
+}
+
 fn color(c: u8, f: &mut impl fmt::Write) -> fmt::Result {
     write!(f, "\x1b[38;5;{c}m")
 }
@@ -406,6 +1073,45 @@ fn normal(f: &mut impl fmt::Write) -> fmt::Result {
     write!(f, "\x1b[0m")
 }
 
+/// The colors `highlight` gives the tokens of `code`, by byte range.
+fn highlights(highlight: &dyn Highlight, code: &str) -> Vec<(Range<usize>, u8)> {
+    highlight
+        .tokens(code)
+        .into_iter()
+        .filter_map(|(range, kind)| Some((range, highlight.color(kind)?)))
+        .collect()
+}
+
+/// Write out `code` with each of `colors` applied to its byte range. Later
+/// colors go over earlier ones.
+fn write_colored(
+    code: &str,
+    colors: &[(Range<usize>, u8)],
+    f: &mut impl fmt::Write,
+) -> fmt::Result {
+    let mut painted = vec![None; code.len()];
+    for (range, c) in colors {
+        let end = range.end.min(code.len());
+        painted[range.start.min(end)..end].fill(Some(*c));
+    }
+
+    let mut start = 0;
+    for run in painted.chunk_by(|a, b| a == b) {
+        let end = start + run.len();
+        match run[0] {
+            Some(c) => {
+                color(c, f)?;
+                write!(f, "{}", &code[start..end])?;
+                normal(f)?;
+            }
+            None => write!(f, "{}", &code[start..end])?,
+        }
+        start = end;
+    }
+
+    Ok(())
+}
+
 fn width(x: usize) -> usize {
     x.checked_ilog10().unwrap_or(1) as usize + 1
 }
@@ -419,3 +1125,72 @@ impl<T: fmt::Display> fmt::Display for Repeat<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Applicability, Code, Help, Plain, SuggestionStyle};
+
+    /// How `help` displays under an error, without the error itself.
+    fn shown(help: Help) -> String {
+        let mut error = crate::error! {
+            "E0001", "oops";
+            "src/main.rs", 1, 5;
+            "let x = 1;";
+            4..=4;
+        };
+        let without = Plain(&error).to_string();
+        error.children.push(help.into());
+
+        let with = Plain(&error).to_string();
+        let help = with.strip_prefix(without.trim_end()).unwrap();
+        help.trim_start_matches(['|', ' ', '\n'])
+            .trim_end()
+            .to_string()
+    }
+
+    fn help(message: &str, suggestion: Code, style: SuggestionStyle) -> Help {
+        Help {
+            message: message.to_string(),
+            suggestion: Some(suggestion),
+            style,
+            applicability: Applicability::Unspecified,
+        }
+    }
+
+    #[test]
+    fn suggestion_styles() {
+        let underscore = || Code::suggestion("let x = 1;", "let _x = 1;", 1);
+        let block = "1 | let _x = 1;\n  |     +";
+
+        let shown_as = |message, style| shown(help(message, underscore(), style));
+        assert!(shown_as("prefix it", SuggestionStyle::Verbose).ends_with(block));
+        assert_eq!(
+            shown_as("prefix it", SuggestionStyle::Inline),
+            "help: prefix it: `_`"
+        );
+        assert_eq!(
+            shown_as("prefix it", SuggestionStyle::Hidden),
+            "help: prefix it"
+        );
+        assert_eq!(
+            shown_as("prefix it", SuggestionStyle::Auto),
+            "help: prefix it: `_`"
+        );
+
+        // long messages and labels make `Auto` verbose
+        let long = "if this is intentional, prefix it with an underscore to silence it";
+        assert!(shown_as(long, SuggestionStyle::Auto).ends_with(block));
+        let mut labeled = underscore();
+        labeled[0].marker.as_mut().unwrap().message = Some("here".to_string());
+        let labeled = shown(help("prefix it", labeled, SuggestionStyle::Auto));
+        assert!(labeled.ends_with(&format!("{block} here")), "{labeled}");
+
+        // several changes can't be shown inline
+        let two = Code::suggestion("let x = 1;", "let mut x = 1u8;", 1);
+        let two = shown(help("change it", two, SuggestionStyle::Inline));
+        assert!(
+            two.ends_with("1 | let mut x = 1u8;\n  |     ++++     ++"),
+            "{two}"
+        );
+    }
+}
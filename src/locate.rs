@@ -0,0 +1,428 @@
+//! Span locators: pointing at code by what it says, instead of by column.
+//!
+//! Anything that takes a span, like the [`error!`](crate::error) macro or the
+//! `span` arguments of the CLI, also takes a locator. As a string, a locator
+//! is one of:
+//! - `start-end`: the columns `start..=end`, e.g. `4-8`
+//! - `start-`: from column `start` to the end of the line, e.g. `4-`
+//! - `` `text` ``: the first occurrence of `text`
+//! - `/regex/`: the first match of a [regex](crate::regex)
+//!
+//! Text and regexes can be followed by `#n` to pick the `n`th occurrence
+//! instead, e.g. `` `x`#2 ``. Columns start at 0, and count characters.
+//!
+//! ```rust
+//! use frack::locate::{Locate, Locator};
+//!
+//! let error = frack::error! {
+//!     "E0499", "cannot borrow `x` as mutable more than once at a time";
+//!     "src/main.rs", 4, 14;
+//!     "    let b = &mut x;";
+//!     "`&mut x`" => "second mutable borrow occurs here";
+//! };
+//!
+//! let code = "    foo(x, x);";
+//! let second = Locator::text("x").nth(2);
+//! assert_eq!(second.locate(code).unwrap(), 11..=11);
+//! assert_eq!("/\\w+/".locate(code).unwrap(), 4..=6);
+//!
+//! // columns must be in order, and occurrences count from 1
+//! assert!("6-4".locate(code).is_err());
+//! assert!("0-100".locate(code).is_err());
+//! assert!((6..=4).locate(code).is_err());
+//! assert_eq!(
+//!     "`x`#0".parse::<Locator>().unwrap_err().to_string(),
+//!     "occurrences of `x` are counted from 1, so there's no occurrence #0",
+//! );
+//! ```
+
+use std::{fmt, ops::RangeInclusive, str::FromStr};
+
+use crate::edit;
+use crate::regex::{Regex, RegexError, TooComplex};
+
+/// Something that can find the columns of a line of code to underline.
+pub trait Locate {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError>;
+}
+
+/// Columns, as they are, if they're in order and in the line.
+impl Locate for RangeInclusive<usize> {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError> {
+        columns(self, code.chars().count())
+    }
+}
+
+/// Check that `range` is in order and within a line of `len` characters.
+///
+/// A single column just past the end is fine: that's where something missing
+/// from the end of the line goes.
+fn columns(
+    range: &RangeInclusive<usize>,
+    len: usize,
+) -> Result<RangeInclusive<usize>, LocateError> {
+    let (start, end) = (*range.start(), *range.end());
+    if start > end {
+        Err(LocateError::Reversed { start, end })
+    } else if end > len || end == len && start < len {
+        Err(LocateError::PastEnd { col: end, len })
+    } else {
+        Ok(range.clone())
+    }
+}
+
+/// A locator, [parsed](Locator::from_str) from the string.
+impl Locate for str {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError> {
+        self.parse::<Locator>()?.locate(code)
+    }
+}
+
+impl Locate for String {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError> {
+        self.as_str().locate(code)
+    }
+}
+
+impl<T: Locate + ?Sized> Locate for &T {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError> {
+        (**self).locate(code)
+    }
+}
+
+/// Where to point in a line of code.
+#[derive(Clone, Debug)]
+pub enum Locator {
+    /// The columns `start..=end`.
+    Columns(RangeInclusive<usize>),
+
+    /// From a column to the end of the line.
+    ToEnd(usize),
+
+    /// The `nth` occurrence of some text, counting from 1.
+    Text { text: String, nth: usize },
+
+    /// The `nth` match of a regex, counting from 1. Empty matches don't count.
+    Regex { regex: Regex, nth: usize },
+}
+
+impl Locator {
+    /// The first occurrence of `text`.
+    pub fn text(text: impl ToString) -> Self {
+        Self::Text {
+            text: text.to_string(),
+            nth: 1,
+        }
+    }
+
+    /// The first match of `pattern`.
+    pub fn regex(pattern: &str) -> Result<Self, LocateError> {
+        Ok(Self::Regex {
+            regex: Regex::new(pattern).map_err(LocateError::Regex)?,
+            nth: 1,
+        })
+    }
+
+    /// Pick the `nth` occurrence instead, counting from 1.
+    ///
+    /// Does nothing for columns.
+    pub fn nth(mut self, n: usize) -> Self {
+        if let Self::Text { nth, .. } | Self::Regex { nth, .. } = &mut self {
+            *nth = n;
+        }
+
+        self
+    }
+
+    /// The line number (starting at 1) and columns this locates in `source`.
+    ///
+    /// Occurrences are counted across every line, but never span lines.
+    /// Columns are in the first line.
+    pub fn find(&self, source: &str) -> Result<(usize, RangeInclusive<usize>), LocateError> {
+        let (Self::Text { nth, .. } | Self::Regex { nth, .. }) = self else {
+            let first = source.lines().next().unwrap_or("");
+            return Ok((1, self.locate(first)?));
+        };
+
+        if *nth == 0 {
+            return Err(self.zeroth());
+        }
+
+        let mut found = 0;
+        for (i, line) in source.lines().enumerate() {
+            let matches = self.matches(line)?;
+            match matches.get(nth.saturating_sub(1 + found)) {
+                Some(range) if found < *nth => return Ok((i + 1, range.clone())),
+                _ => found += matches.len(),
+            }
+        }
+
+        Err(self.not_found(found))
+    }
+
+    /// The columns of every occurrence of the text or regex in `code`.
+    fn matches(&self, code: &str) -> Result<Vec<RangeInclusive<usize>>, LocateError> {
+        let col = |byte| edit::column(code, byte);
+        let ranges: Vec<_> = match self {
+            Self::Text { text, .. } if !text.is_empty() => code
+                .match_indices(text.as_str())
+                .map(|(i, t)| i..i + t.len())
+                .collect(),
+            Self::Regex { regex, .. } => regex
+                .try_find_iter(code)
+                .filter(|r| r.as_ref().map_or(true, |r| r.start != r.end))
+                .collect::<Result<_, TooComplex>>()
+                .map_err(|_| LocateError::TooComplex {
+                    locator: self.clone().nth(1).to_string(),
+                })?,
+            _ => Vec::new(),
+        };
+
+        Ok(ranges
+            .into_iter()
+            .map(|r| col(r.start)..=col(r.end) - 1)
+            .collect())
+    }
+
+    /// The error for asking for occurrence #0.
+    fn zeroth(&self) -> LocateError {
+        LocateError::ZerothOccurrence {
+            locator: self.clone().nth(1).to_string(),
+        }
+    }
+
+    fn not_found(&self, found: usize) -> LocateError {
+        let nth = match self {
+            Self::Text { nth, .. } | Self::Regex { nth, .. } => *nth,
+            _ => 1,
+        };
+
+        LocateError::NotFound {
+            locator: self.to_string(),
+            nth,
+            found,
+        }
+    }
+}
+
+impl Locate for Locator {
+    fn locate(&self, code: &str) -> Result<RangeInclusive<usize>, LocateError> {
+        let len = code.chars().count();
+        match self {
+            Self::Columns(range) => columns(range, len),
+
+            Self::ToEnd(col) if *col > len => Err(LocateError::PastEnd { col: *col, len }),
+            Self::ToEnd(col) => Ok(*col..=len.saturating_sub(1).max(*col)),
+
+            Self::Text { nth: 0, .. } | Self::Regex { nth: 0, .. } => Err(self.zeroth()),
+            Self::Text { nth, .. } | Self::Regex { nth, .. } => {
+                let matches = self.matches(code)?;
+                matches
+                    .get(nth - 1)
+                    .cloned()
+                    .ok_or_else(|| self.not_found(matches.len()))
+            }
+        }
+    }
+}
+
+/// Parses the syntax described in the [module docs](self).
+impl FromStr for Locator {
+    type Err = LocateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax = || LocateError::Syntax(s.to_string());
+
+        // `text` or /regex/, then an optional #n
+        for delim in ['`', '/'] {
+            let Some(rest) = s.strip_prefix(delim) else {
+                continue;
+            };
+
+            let (inner, nth) = rest.rsplit_once(delim).ok_or_else(syntax)?;
+            let nth = match nth {
+                "" => 1,
+                n => n
+                    .strip_prefix('#')
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(syntax)?,
+            };
+
+            let locator = match delim {
+                '`' => Self::text(inner),
+                _ => Self::regex(inner)?,
+            };
+
+            if nth == 0 {
+                return Err(locator.zeroth());
+            }
+
+            return Ok(locator.nth(nth));
+        }
+
+        let (start, end) = s.split_once('-').ok_or_else(syntax)?;
+        let start = start.parse().map_err(|_| syntax())?;
+        match end {
+            "" => Ok(Self::ToEnd(start)),
+            end => Ok(Self::Columns(start..=end.parse().map_err(|_| syntax())?)),
+        }
+    }
+}
+
+impl fmt::Display for Locator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Columns(range) => write!(f, "{}-{}", range.start(), range.end()),
+            Self::ToEnd(col) => write!(f, "{col}-"),
+            Self::Text { text, nth } => {
+                write!(f, "`{text}`")?;
+                if *nth != 1 {
+                    write!(f, "#{nth}")?;
+                }
+
+                Ok(())
+            }
+            Self::Regex { regex, nth } => {
+                write!(f, "/{regex}/")?;
+                if *nth != 1 {
+                    write!(f, "#{nth}")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Why a [`Locator`] couldn't be parsed or located.
+#[derive(Debug)]
+pub enum LocateError {
+    /// The string isn't a locator.
+    Syntax(String),
+
+    /// The locator's regex is invalid.
+    Regex(RegexError),
+
+    /// The text or regex occurs fewer than `nth` times.
+    NotFound {
+        locator: String,
+        nth: usize,
+        found: usize,
+    },
+
+    /// A column is past the end of the line.
+    PastEnd { col: usize, len: usize },
+
+    /// The columns `start-end` end before they start.
+    Reversed { start: usize, end: usize },
+
+    /// The text or regex was given `#0`, but occurrences count from 1.
+    ZerothOccurrence { locator: String },
+
+    /// The regex backtracked so much that the search gave up.
+    TooComplex { locator: String },
+}
+
+impl fmt::Display for LocateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(s) => write!(
+                f,
+                "invalid span {s:?}: expected `start-end`, `start-`, a `text` in backticks, or a /regex/"
+            ),
+            Self::Regex(err) => write!(f, "invalid regex: {err}"),
+            Self::NotFound {
+                locator, found: 0, ..
+            } => write!(f, "{locator} doesn't occur in the code"),
+            Self::NotFound {
+                locator,
+                nth,
+                found,
+            } => {
+                let times = if *found == 1 { "once" } else { "times" };
+                let found = if *found == 1 {
+                    String::new()
+                } else {
+                    format!("{found} ")
+                };
+
+                write!(f, "{locator} only occurs {found}{times}, so there's no occurrence #{nth}")
+            }
+            Self::PastEnd { col, len } => write!(
+                f,
+                "column {col} is past the end of the line, which is {len} characters long"
+            ),
+            Self::Reversed { start, end } => {
+                write!(f, "span {start}-{end} ends before it starts")
+            }
+            Self::ZerothOccurrence { locator } => write!(
+                f,
+                "occurrences of {locator} are counted from 1, so there's no occurrence #0"
+            ),
+            Self::TooComplex { locator } => write!(
+                f,
+                "{locator} backtracks too much to search for, so the search gave up"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LocateError {}
+
+/// What the [`error!`](crate::error) and [`warning!`](crate::warning) macros
+/// use to resolve their spans.
+#[doc(hidden)]
+pub fn expect(locator: &(impl Locate + ?Sized), code: &str) -> RangeInclusive<usize> {
+    locator.locate(code).unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locate, LocateError, Locator, RangeInclusive};
+
+    #[test]
+    fn columns_stay_in_the_line() {
+        let code = "let x = x;";
+        assert_eq!("4-4".locate(code).unwrap(), 4..=4);
+        assert_eq!("10-10".locate(code).unwrap(), 10..=10);
+        assert_eq!((0..=9).locate(code).unwrap(), 0..=9);
+
+        for span in ["0-100", "9-10", "11-11"] {
+            let err = span.locate(code).unwrap_err();
+            assert!(
+                matches!(err, LocateError::PastEnd { len: 10, .. }),
+                "{span}"
+            );
+        }
+
+        assert!(matches!(
+            (0..=100).locate(code),
+            Err(LocateError::PastEnd { col: 100, len: 10 })
+        ));
+        assert!(matches!(
+            RangeInclusive::new(6, 4).locate(code),
+            Err(LocateError::Reversed { start: 6, end: 4 })
+        ));
+    }
+
+    #[test]
+    fn regexes_that_give_up_are_errors() {
+        let code = "a".repeat(40);
+        let err = "/(a|a)*b/".locate(&code).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "/(a|a)*b/ backtracks too much to search for, so the search gave up"
+        );
+
+        let source = format!("let x = 1;\n{code}\n");
+        let locator = Locator::regex("(a|a)*b").unwrap();
+        assert!(matches!(
+            locator.find(&source),
+            Err(LocateError::TooComplex { .. })
+        ));
+
+        // finding nothing is still just not found
+        let err = "/(a|a)*b/".locate("aaa").unwrap_err();
+        assert!(matches!(err, LocateError::NotFound { found: 0, .. }));
+    }
+}
@@ -0,0 +1,769 @@
+//! A Language Server Protocol server that publishes diagnostics from a file.
+//!
+//! The file holds diagnostics in `rustc`'s JSON format (see [`json`](crate::json)),
+//! one per line, and is reloaded whenever it changes. Suggestions are offered
+//! as quick-fix code actions.
+//!
+//! The server talks over any reader and writer, so it can be driven by a
+//! scripted client as easily as by an editor:
+//!
+//! ```rust,no_run
+//! use frack::json::Value;
+//! use frack::lsp::{self, Server};
+//!
+//! let mut script = Vec::new();
+//! for (id, method) in [(Some(1), "initialize"), (None, "initialized"), (Some(2), "shutdown"), (None, "exit")] {
+//!     let mut message = vec![("jsonrpc", "2.0".into()), ("method", method.into())];
+//!     if let Some(id) = id {
+//!         message.push(("id", Value::from(id as usize)));
+//!     }
+//!     let message = Value::Object(message.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+//!     lsp::write_message(&mut script, &message).unwrap();
+//! }
+//!
+//! let mut output = Vec::new();
+//! Server::new("diagnostics.json")
+//!     .run(std::io::Cursor::new(script), &mut output)
+//!     .unwrap();
+//! ```
+
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::json::{self, Value};
+use crate::{descendants, edit, Applicability, ChildKind, Diagnostic, Level, Line};
+
+/// A language server publishing the diagnostics in a file.
+pub struct Server {
+    path: PathBuf,
+    poll: Duration,
+    source: String,
+    code_url: Option<String>,
+}
+
+impl Server {
+    /// Create a server for the diagnostics in the file at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            poll: Duration::from_millis(500),
+            source: "frack".to_string(),
+            code_url: Some("https://doc.rust-lang.org/error_codes/{code}.html".to_string()),
+        }
+    }
+
+    /// Set how often to check the file for changes. Defaults to 500ms.
+    pub fn poll_interval(mut self, poll: Duration) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    /// Set the `source` of published diagnostics. Defaults to `frack`.
+    pub fn source(mut self, source: impl ToString) -> Self {
+        self.source = source.to_string();
+        self
+    }
+
+    /// Set the documentation link for error codes, with `{code}` standing in for
+    /// the code. `None` disables links.
+    ///
+    /// Defaults to `rustc`'s error index; only codes like `E0308` get links.
+    pub fn code_url(mut self, url: Option<String>) -> Self {
+        self.code_url = url;
+        self
+    }
+
+    /// Serve a client until it sends `exit` or closes `input`.
+    ///
+    /// `input` is read on a separate thread, so the file can be checked for
+    /// changes while waiting for messages.
+    pub fn run<R, W>(&self, input: R, mut output: W) -> io::Result<()>
+    where
+        R: BufRead + Send + 'static,
+        W: Write,
+    {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut input = input;
+            loop {
+                let message = read_message(&mut input);
+                let done = !matches!(message, Ok(Some(_)));
+                if tx.send(message).is_err() || done {
+                    break;
+                }
+            }
+        });
+
+        let mut state = State {
+            server: self,
+            root: None,
+            modified: None,
+            initialized: false,
+            shutdown: false,
+            published: Vec::new(),
+        };
+
+        loop {
+            match rx.recv_timeout(self.poll) {
+                Ok(Ok(Some(message))) => {
+                    if !state.handle(&message, &mut output)? {
+                        return Ok(());
+                    }
+                }
+                Ok(Ok(None)) | Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                Ok(Err(e)) => return Err(e),
+                Err(RecvTimeoutError::Timeout) => {
+                    if state.initialized && state.modified != modified(&self.path) {
+                        state.publish(&mut output)?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The longest message [`read_message`] accepts, in bytes.
+const MAX_LENGTH: usize = 64 << 20;
+
+/// Read a single message, framed with a `Content-Length` header.
+///
+/// Returns `None` at the end of the input. Messages that aren't valid JSON are
+/// returned as [`Value::Null`], and messages longer than 64 MiB are an error.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let length = length.unwrap_or(0);
+    if length > MAX_LENGTH {
+        let message = format!("message of {length} bytes is too long");
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    // read what's actually sent, rather than trusting the length up front
+    let mut body = Vec::new();
+    input.take(length as u64).read_to_end(&mut body)?;
+    if body.len() < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+
+    let body = String::from_utf8_lossy(&body);
+    Ok(Some(json::parse(&body).unwrap_or(Value::Null)))
+}
+
+/// Write a single message, framed with a `Content-Length` header.
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
+
+/// A published diagnostic, with the code actions that fix it.
+struct Published {
+    uri: String,
+    diagnostic: Value,
+    actions: Vec<Value>,
+}
+
+struct State<'a> {
+    server: &'a Server,
+    root: Option<String>,
+    modified: Option<SystemTime>,
+    initialized: bool,
+    shutdown: bool,
+    published: Vec<Published>,
+}
+
+impl State<'_> {
+    /// Handle a message. Returns `false` once the client sends `exit`.
+    fn handle(&mut self, message: &Value, output: &mut impl Write) -> io::Result<bool> {
+        let method = message.get("method").and_then(Value::as_str);
+        let id = message.get("id").cloned();
+        let params = message.get("params").unwrap_or(&Value::Null);
+
+        match (method, id) {
+            (Some("initialize"), Some(id)) => {
+                self.root = params
+                    .get("rootUri")
+                    .and_then(Value::as_str)
+                    .or_else(|| {
+                        params
+                            .get("workspaceFolders")
+                            .and_then(Value::as_array)
+                            .and_then(|f| f.first())
+                            .and_then(|f| f.get("uri"))
+                            .and_then(Value::as_str)
+                    })
+                    .map(|r| r.trim_end_matches('/').to_string());
+
+                let capabilities = Value::object([
+                    (
+                        "textDocumentSync",
+                        Value::object([("openClose", true.into()), ("change", 0.into())]),
+                    ),
+                    (
+                        "codeActionProvider",
+                        Value::object([("codeActionKinds", Value::Array(vec!["quickfix".into()]))]),
+                    ),
+                ]);
+
+                let result = Value::object([
+                    ("capabilities", capabilities),
+                    (
+                        "serverInfo",
+                        Value::object([
+                            ("name", "frack".into()),
+                            ("version", env!("CARGO_PKG_VERSION").into()),
+                        ]),
+                    ),
+                ]);
+
+                respond(output, id, result)?;
+            }
+
+            (Some("initialized"), None) => {
+                self.initialized = true;
+                self.publish(output)?;
+            }
+
+            (Some("shutdown"), Some(id)) => {
+                self.shutdown = true;
+                respond(output, id, Value::Null)?;
+            }
+
+            (Some("exit"), _) => return Ok(false),
+
+            (Some("textDocument/codeAction"), Some(id)) => {
+                let actions = self.actions(params);
+                respond(output, id, Value::Array(actions))?;
+            }
+
+            (Some(_), Some(id)) => {
+                let code = if self.shutdown { -32600.0 } else { -32601.0 };
+                let error = Value::object([
+                    ("code", Value::Number(code)),
+                    ("message", "method not supported".into()),
+                ]);
+
+                write_message(
+                    output,
+                    &Value::object([("jsonrpc", "2.0".into()), ("id", id), ("error", error)]),
+                )?;
+            }
+
+            // notifications, e.g. `textDocument/didOpen`, and responses
+            _ => {}
+        }
+
+        Ok(true)
+    }
+
+    /// The code actions for a `textDocument/codeAction` request.
+    fn actions(&self, params: &Value) -> Vec<Value> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Value::as_str);
+        let line = |pos: &str| {
+            params
+                .get("range")
+                .and_then(|r| r.get(pos))
+                .and_then(|p| p.get("line"))
+                .and_then(Value::as_usize)
+        };
+        let (start, end) = (
+            line("start").unwrap_or(0),
+            line("end").unwrap_or(usize::MAX),
+        );
+
+        let mut actions = Vec::new();
+        for p in self
+            .published
+            .iter()
+            .filter(|p| Some(p.uri.as_str()) == uri)
+        {
+            let range = p.diagnostic.get("range");
+            let line = |pos: &str| {
+                range
+                    .and_then(|r| r.get(pos))
+                    .and_then(|p| p.get("line"))
+                    .and_then(Value::as_usize)
+                    .unwrap_or(0)
+            };
+
+            if line("start") > end || line("end") < start {
+                continue;
+            }
+
+            for action in &p.actions {
+                let mut action = action.clone();
+                if let Value::Object(fields) = &mut action {
+                    fields.insert(
+                        2,
+                        (
+                            "diagnostics".to_string(),
+                            Value::Array(vec![p.diagnostic.clone()]),
+                        ),
+                    );
+                }
+                actions.push(action);
+            }
+        }
+
+        actions
+    }
+
+    /// Reload the file and publish its diagnostics, clearing those of files
+    /// that no longer have any.
+    fn publish(&mut self, output: &mut impl Write) -> io::Result<()> {
+        self.modified = modified(&self.server.path);
+        let old: Vec<String> = self.published.drain(..).map(|p| p.uri).collect();
+
+        match std::fs::File::open(&self.server.path) {
+            Ok(file) => {
+                for message in json::Stream::new(io::BufReader::new(file)) {
+                    match message {
+                        Ok(message) => {
+                            let published = self.convert(&message.diagnostic);
+                            self.published.push(published);
+                        }
+                        Err(e) => self.log(output, 2, &e.to_string())?,
+                    }
+                }
+            }
+
+            Err(e) => {
+                let message = format!("failed to read {}: {e}", self.server.path.display());
+                self.log(output, 1, &message)?;
+            }
+        }
+
+        let mut uris: Vec<&str> = Vec::new();
+        for uri in self
+            .published
+            .iter()
+            .map(|p| p.uri.as_str())
+            .chain(old.iter().map(String::as_str))
+        {
+            if !uris.contains(&uri) {
+                uris.push(uri);
+            }
+        }
+
+        for uri in uris {
+            let diagnostics = self
+                .published
+                .iter()
+                .filter(|p| p.uri == uri)
+                .map(|p| p.diagnostic.clone())
+                .collect::<Vec<_>>();
+
+            notify(
+                output,
+                "textDocument/publishDiagnostics",
+                Value::object([("uri", uri.into()), ("diagnostics", diagnostics.into())]),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn log(&self, output: &mut impl Write, kind: usize, message: &str) -> io::Result<()> {
+        notify(
+            output,
+            "window/logMessage",
+            Value::object([("type", kind.into()), ("message", message.into())]),
+        )
+    }
+
+    /// Convert a diagnostic to LSP's format.
+    fn convert(&self, diag: &Diagnostic) -> Published {
+        let file = diag.file();
+        let uri = self.uri(&file.path);
+        let primary = diag.primary();
+
+        let primary_range = match primary {
+            Some(line) => {
+                let marked = line
+                    .marker
+                    .as_ref()
+                    .map_or(0..0, |m| edit::marked(line, &m.range));
+                range(line, marked)
+            }
+
+            None => {
+                let pos = position(file.line.saturating_sub(1), file.col.saturating_sub(1));
+                Value::object([("start", pos.clone()), ("end", pos)])
+            }
+        };
+
+        let mut message = diag.message().to_string();
+        if let Some(label) = diag.label() {
+            message.push('\n');
+            message.push_str(label);
+        }
+        for (depth, child) in descendants(diag.children()) {
+            let (level, text) = match &child.kind {
+                ChildKind::Help(h) if h.suggestion.is_some() => continue,
+                ChildKind::Help(h) => ("help", &h.message),
+                ChildKind::Note(n) => ("note", &n.0),
+                ChildKind::Warning(n) => ("warning", &n.0),
+            };
+            message.push('\n');
+            message.push_str(&"  ".repeat(depth));
+            message.push_str(&format!("{level}: {text}"));
+        }
+
+        let severity: usize = match diag.level() {
+            Level::Error => 1,
+            Level::Warning => 2,
+        };
+
+        let mut fields = vec![
+            ("range".to_string(), primary_range),
+            ("severity".to_string(), severity.into()),
+        ];
+
+        if let Some(code) = diag.error_code() {
+            fields.push(("code".to_string(), code.into()));
+
+            let is_rustc_code = code.len() == 5
+                && code.starts_with('E')
+                && code[1..].bytes().all(|b| b.is_ascii_digit());
+            let url = self
+                .server
+                .code_url
+                .as_ref()
+                .filter(|url| is_rustc_code || !url.starts_with("https://doc.rust-lang.org/"));
+            if let Some(url) = url {
+                fields.push((
+                    "codeDescription".to_string(),
+                    Value::object([("href", url.replace("{code}", code).into())]),
+                ));
+            }
+        }
+
+        fields.push(("source".to_string(), self.server.source.as_str().into()));
+        fields.push(("message".to_string(), message.into()));
+
+        let related = diag
+            .code()
+            .iter()
+            .filter(|l| !primary.is_some_and(|p| std::ptr::eq(p, *l)))
+            .filter_map(|line| {
+                let m = line.marker.as_ref()?;
+                Some(Value::object([
+                    (
+                        "location",
+                        Value::object([
+                            ("uri", uri.as_str().into()),
+                            ("range", range(line, edit::marked(line, &m.range))),
+                        ]),
+                    ),
+                    ("message", m.message.as_deref().unwrap_or("").into()),
+                ]))
+            })
+            .collect::<Vec<_>>();
+
+        if !related.is_empty() {
+            fields.push(("relatedInformation".to_string(), related.into()));
+        }
+
+        let actions = diag
+            .helps()
+            .filter_map(|help| {
+                let edits = edit::suggested(diag.code(), help.suggestion.as_ref()?)
+                    .into_iter()
+                    .map(|(line, e)| {
+                        Value::object([
+                            ("range", range(line, e.range)),
+                            ("newText", e.replacement.into()),
+                        ])
+                    })
+                    .collect::<Vec<_>>();
+
+                if edits.is_empty() {
+                    return None;
+                }
+
+                Some(Value::object([
+                    ("title", help.message.as_str().into()),
+                    ("kind", "quickfix".into()),
+                    (
+                        "isPreferred",
+                        (help.applicability == Applicability::MachineApplicable).into(),
+                    ),
+                    (
+                        "edit",
+                        Value::object([(
+                            "changes",
+                            Value::Object(vec![(uri.clone(), edits.into())]),
+                        )]),
+                    ),
+                ]))
+            })
+            .collect();
+
+        Published {
+            uri,
+            diagnostic: Value::Object(fields),
+            actions,
+        }
+    }
+
+    /// The URI of a path, resolved against the workspace root.
+    fn uri(&self, path: &str) -> String {
+        let path = path.replace('\\', "/");
+        let path = path.trim_start_matches("./");
+
+        let mut encoded = String::new();
+        for b in path.bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    encoded.push(b as char)
+                }
+                _ => encoded.push_str(&format!("%{b:02X}")),
+            }
+        }
+
+        if path.starts_with('/') {
+            return format!("file://{encoded}");
+        }
+
+        match &self.root {
+            Some(root) => format!("{root}/{encoded}"),
+            None => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                let cwd = cwd.to_string_lossy().replace('\\', "/");
+                format!("file://{}/{encoded}", cwd.trim_end_matches('/'))
+            }
+        }
+    }
+}
+
+fn respond(output: &mut impl Write, id: Value, result: Value) -> io::Result<()> {
+    write_message(
+        output,
+        &Value::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+    )
+}
+
+fn notify(output: &mut impl Write, method: &str, params: Value) -> io::Result<()> {
+    write_message(
+        output,
+        &Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("method", method.into()),
+            ("params", params),
+        ]),
+    )
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// A range on a single line, in UTF-16 code units as LSP expects.
+fn range(line: &Line, range: std::ops::Range<usize>) -> Value {
+    let character = |byte: usize| {
+        line.code
+            .get(..byte)
+            .map_or(byte, |s| s.encode_utf16().count())
+    };
+
+    let l = line.line_number.saturating_sub(1);
+    Value::object([
+        ("start", position(l, character(range.start))),
+        ("end", position(l, character(range.end))),
+    ])
+}
+
+fn position(line: usize, character: usize) -> Value {
+    Value::object([("line", line.into()), ("character", character.into())])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{read_message, write_message, Server};
+    use crate::json::{self, Value};
+    use crate::{fix, frackfile};
+
+    const SOURCE: &str = "let x = 1;\nx += 1;\n";
+
+    const FRACK: &str = "\
+error: cannot assign twice to immutable variable `x`
+ --> src/main.rs:2:1
+  |
+2 | x += 1;
+  | ^^^^^^ cannot assign twice to immutable variable
+  |
+help: consider making this binding mutable
+  |
+1 | let mut x = 1;
+  |     ++++
+";
+
+    /// A JSON-RPC message, framed as a client would send it.
+    fn framed(id: Option<usize>, method: &str, params: Value) -> Vec<u8> {
+        let mut message = Value::object([("jsonrpc", "2.0".into())]);
+        if let Value::Object(fields) = &mut message {
+            fields.extend(id.map(|id| ("id".to_string(), id.into())));
+            fields.push(("method".to_string(), method.into()));
+            fields.push(("params".to_string(), params));
+        }
+
+        let mut out = Vec::new();
+        write_message(&mut out, &message).unwrap();
+        out
+    }
+
+    /// Run the server on `FRACK` with a scripted session, returning what it sends.
+    fn session() -> Vec<Value> {
+        let path = std::env::temp_dir().join(format!("frack-lsp-{}.json", std::process::id()));
+        let diags = frackfile::parse(FRACK).unwrap();
+        std::fs::write(&path, format!("{}\n", json::to_json(&diags[0]))).unwrap();
+
+        let document = || Value::object([("uri", "file:///project/src/main.rs".into())]);
+        let position =
+            |line: usize| Value::object([("line", line.into()), ("character", 0.into())]);
+
+        let mut input = Vec::new();
+        input.extend(framed(
+            Some(1),
+            "initialize",
+            Value::object([("rootUri", "file:///project/".into())]),
+        ));
+        input.extend(framed(None, "initialized", Value::object([])));
+        input.extend(framed(
+            Some(2),
+            "textDocument/codeAction",
+            Value::object([
+                ("textDocument", document()),
+                (
+                    "range",
+                    Value::object([("start", position(1)), ("end", position(1))]),
+                ),
+            ]),
+        ));
+        input.extend(framed(Some(3), "shutdown", Value::Null));
+        input.extend(framed(None, "exit", Value::Null));
+
+        let mut output = Vec::new();
+        let result = Server::new(&path).run(Cursor::new(input), &mut output);
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let mut output = Cursor::new(output);
+        std::iter::from_fn(|| read_message(&mut output).unwrap()).collect()
+    }
+
+    /// `SOURCE` with the edits of a code action made.
+    fn edited(action: &Value) -> String {
+        let changes = action.get("edit").and_then(|e| e.get("changes")).unwrap();
+        let edits = changes.get("file:///project/src/main.rs").unwrap();
+
+        let mut lines: Vec<String> = SOURCE.lines().map(str::to_string).collect();
+        for edit in edits.as_array().unwrap().iter().rev() {
+            let pos = |key| {
+                let pos = edit.get("range").and_then(|r| r.get(key)).unwrap();
+                let n = |key| pos.get(key).and_then(Value::as_usize).unwrap();
+                (n("line"), n("character"))
+            };
+            let ((line, start), (end_line, end)) = (pos("start"), pos("end"));
+            assert_eq!(line, end_line);
+            let text = edit.get("newText").and_then(Value::as_str).unwrap();
+            lines[line].replace_range(start..end, text);
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    #[test]
+    fn publishes_diagnostics_and_code_actions() {
+        let messages = session();
+        let method = |m: &Value| m.get("method").and_then(Value::as_str).map(str::to_string);
+        let id = |m: &Value| m.get("id").and_then(Value::as_usize);
+
+        let publish = messages
+            .iter()
+            .find(|m| method(m).as_deref() == Some("textDocument/publishDiagnostics"))
+            .and_then(|m| m.get("params"))
+            .unwrap();
+        assert_eq!(
+            publish.get("uri").and_then(Value::as_str),
+            Some("file:///project/src/main.rs")
+        );
+        let diagnostics = publish
+            .get("diagnostics")
+            .and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        let range = diagnostics[0].get("range").unwrap();
+        let n = |pos: &str, key: &str| {
+            range
+                .get(pos)
+                .and_then(|p| p.get(key))
+                .and_then(Value::as_usize)
+        };
+        assert_eq!(
+            (n("start", "line"), n("start", "character")),
+            (Some(1), Some(0))
+        );
+        assert_eq!(
+            (n("end", "line"), n("end", "character")),
+            (Some(1), Some(6))
+        );
+
+        let actions = messages
+            .iter()
+            .find(|m| id(m) == Some(2))
+            .and_then(|m| m.get("result"))
+            .and_then(Value::as_array)
+            .unwrap();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(
+            actions[0].get("title").and_then(Value::as_str),
+            Some("consider making this binding mutable")
+        );
+
+        let fixes = fix::fixes(&frackfile::parse(FRACK).unwrap());
+        assert_eq!(edited(&actions[0]), fix::apply(SOURCE, &fixes).source);
+        assert_eq!(edited(&actions[0]), "let mut x = 1;\nx += 1;\n");
+
+        assert!(messages.iter().any(|m| id(m) == Some(3)));
+    }
+
+    #[test]
+    fn long_messages_are_rejected() {
+        let mut input = Cursor::new(format!("Content-Length: {}\r\n\r\n{{}}", usize::MAX));
+        let error = read_message(&mut input).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut input = Cursor::new("Content-Length: 10\r\n\r\n{}");
+        let error = read_message(&mut input).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
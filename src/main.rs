@@ -2,38 +2,520 @@ use frack::*;
 
 use std::iter::Peekable;
 
-fn main() {
-    let mut args = std::env::args();
-    let exe = args.next().unwrap();
-
-    let Some(command) = args.next() else {
-        let start = exe.len() + 2;
-        let err = error! {
-            "MISSING", "must provide command";
-            "arg", 1, 1;
-            exe;
-            start..=start + 3 => "no command provided";
-            help "try `frack help` for usage";
-        };
+/// How to print the generated diagnostic.
+#[derive(Clone, Copy)]
+enum Format {
+    Human,
+    Json,
+    Short,
+    Sarif,
+    GitHub,
+    GitLab,
+    JUnit,
+    Checkstyle,
+    Gcc,
+    Clang,
+    Tsc,
+    Python,
+    Fancy,
+}
 
-        eprintln!("{err}");
-        std::process::exit(1);
+const FORMATS: &[&str] = &[
+    "human",
+    "json",
+    "short",
+    "sarif",
+    "github",
+    "gitlab",
+    "junit",
+    "checkstyle",
+    "gcc",
+    "clang",
+    "tsc",
+    "python",
+    "fancy",
+];
+
+impl Format {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "human" => Some(Self::Human),
+            "json" => Some(Self::Json),
+            "short" => Some(Self::Short),
+            "sarif" => Some(Self::Sarif),
+            "github" => Some(Self::GitHub),
+            "gitlab" => Some(Self::GitLab),
+            "junit" => Some(Self::JUnit),
+            "checkstyle" => Some(Self::Checkstyle),
+            "gcc" => Some(Self::Gcc),
+            "clang" => Some(Self::Clang),
+            "tsc" => Some(Self::Tsc),
+            "python" => Some(Self::Python),
+            "fancy" => Some(Self::Fancy),
+            _ => None,
+        }
+    }
+    /// Whether diagnostics can be printed one at a time, rather than as a
+    /// single document.
+    fn streams(self) -> bool {
+        !matches!(
+            self,
+            Self::Sarif | Self::GitLab | Self::JUnit | Self::Checkstyle
+        )
+    }
+
+    fn emit(self, diags: Vec<Diagnostic>) {
+        match self {
+            Self::Sarif => {
+                let sarif = sarif::Sarif::new("frack").version(env!("CARGO_PKG_VERSION"));
+                println!("{}", sarif.log(&diags));
+            }
+
+            Self::GitLab => println!("{}", ci::gitlab(&diags)),
+            Self::JUnit => print!("{}", xml::junit("frack", &diags)),
+            Self::Checkstyle => print!("{}", xml::checkstyle(&diags)),
+
+            _ => {
+                for diag in diags {
+                    match self {
+                        Self::Json => println!("{}", json::to_json(&diag)),
+                        Self::Short => print!("{}", short::Short::new(&diag)),
+                        Self::GitHub => print!("{}", ci::GitHub(&diag)),
+                        Self::Gcc => print!("{}", style::Styled::new(&diag, &style::Gcc)),
+                        Self::Clang => print!("{}", style::Styled::new(&diag, &style::Clang)),
+                        Self::Tsc => print!("{}", style::Styled::new(&diag, &style::Tsc)),
+                        Self::Python => print!("{}", style::Styled::new(&diag, &style::Python)),
+                        Self::Fancy => {
+                            let fancy = style::Fancy::detect();
+                            print!("{}", style::Styled::new(&diag, &fancy));
+                        }
+                        _ => println!("{diag}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+frack::codes! {
+    /// The codes of the `rustc` errors `frack template` creates, for
+    /// `frack --explain`.
+    static CODES = "frack";
+
+    E0308 => include_str!("explain/E0308.md"),
+    E0382 => include_str!("explain/E0382.md"),
+    E0425 => include_str!("explain/E0425.md"),
+    E0502 => include_str!("explain/E0502.md"),
+    E0599 => include_str!("explain/E0599.md"),
+}
+
+/// An option, e.g. `-f, --format <format>`.
+struct Opt {
+    long: &'static str,
+    short: char,
+
+    /// The name of the option's value, if it takes one.
+    value: Option<&'static str>,
+}
+
+/// A command, e.g. `frack render`.
+struct Command {
+    name: &'static str,
+
+    /// What `frack <command> --help` prints.
+    help: &'static str,
+
+    /// The options only this command takes.
+    options: &'static [Opt],
+
+    /// The most arguments the command takes, if there's a limit.
+    max_args: Option<usize>,
+}
+
+/// The options every command takes.
+const OPTIONS: &[Opt] = &[
+    Opt {
+        long: "help",
+        short: 'h',
+        value: None,
+    },
+    Opt {
+        long: "version",
+        short: 'V',
+        value: None,
+    },
+    Opt {
+        long: "format",
+        short: 'f',
+        value: Some("format"),
+    },
+    Opt {
+        long: "explain",
+        short: 'e',
+        value: Some("code"),
+    },
+];
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        help: include_str!("help/help.txt"),
+        options: &[],
+        max_args: Some(1),
+    },
+    Command {
+        name: "example",
+        help: include_str!("help/example.txt"),
+        options: &[],
+        max_args: Some(0),
+    },
+    Command {
+        name: "error",
+        help: include_str!("help/error.txt"),
+        options: &[Opt {
+            long: "at",
+            short: 'a',
+            value: Some("span"),
+        }],
+        max_args: None,
+    },
+    Command {
+        name: "warning",
+        help: include_str!("help/warning.txt"),
+        options: &[Opt {
+            long: "at",
+            short: 'a',
+            value: Some("span"),
+        }],
+        max_args: None,
+    },
+    Command {
+        name: "template",
+        help: include_str!("help/template.txt"),
+        options: &[Opt {
+            long: "var",
+            short: 'v',
+            value: Some("name=value"),
+        }],
+        max_args: Some(1),
+    },
+    Command {
+        name: "render",
+        help: include_str!("help/render.txt"),
+        options: &[],
+        max_args: Some(1),
+    },
+    Command {
+        name: "render-json",
+        help: include_str!("help/render-json.txt"),
+        options: &[Opt {
+            long: "check",
+            short: 'c',
+            value: None,
+        }],
+        max_args: Some(0),
+    },
+    Command {
+        name: "fix",
+        help: include_str!("help/fix.txt"),
+        options: &[
+            Opt {
+                long: "dry-run",
+                short: 'n',
+                value: None,
+            },
+            Opt {
+                long: "all",
+                short: 'a',
+                value: None,
+            },
+        ],
+        max_args: Some(0),
+    },
+    Command {
+        name: "from-diff",
+        help: include_str!("help/from-diff.txt"),
+        options: &[Opt {
+            long: "message",
+            short: 'm',
+            value: Some("message"),
+        }],
+        max_args: Some(0),
+    },
+    Command {
+        name: "lsp",
+        help: include_str!("help/lsp.txt"),
+        options: &[Opt {
+            long: "poll",
+            short: 'p',
+            value: Some("ms"),
+        }],
+        max_args: Some(1),
+    },
+];
+
+/// An option given on the command line, by long name, with its index and,
+/// if it takes one, the index of its value and the value.
+type Given = (&'static str, usize, Option<(usize, String)>);
+
+/// The parsed command line.
+struct Parsed {
+    command: Option<&'static Command>,
+
+    options: Vec<Given>,
+
+    /// The command's arguments, and their indices.
+    args: Vec<(usize, String)>,
+}
+
+impl Parsed {
+    /// The index of the first use of an option, if it was given.
+    fn index(&self, long: &str) -> Option<usize> {
+        self.options
+            .iter()
+            .find(|(l, _, _)| *l == long)
+            .map(|(_, i, _)| *i)
+    }
+
+    fn flag(&self, long: &str) -> bool {
+        self.index(long).is_some()
+    }
+
+    fn values<'a>(&'a self, long: &'a str) -> impl Iterator<Item = (usize, &'a str)> {
+        self.options
+            .iter()
+            .filter(move |(l, _, _)| *l == long)
+            .filter_map(|(_, _, v)| Some((v.as_ref()?.0, v.as_ref()?.1.as_str())))
+    }
+}
+
+/// Parse the command line, exiting on errors. Options can go anywhere, until
+/// a `--`.
+fn parse(argv: &args::Argv) -> Parsed {
+    let mut parsed = Parsed {
+        command: None,
+        options: Vec::new(),
+        args: Vec::new(),
     };
 
-    match command.as_str() {
-        "help" => {
-            let help = include_str!("help.txt")
-                .replace("{OFF}", "\x1b[0m")
-                .replace("{BOLD}", "\x1b[1m")
-                .replace("{EMPH}", "\x1b[4m")
-                .replace("{RED}", "\x1b[38;5;9m")
-                .replace("{GREEN}", "\x1b[38;5;2m")
-                .replace("{YELLOW}", "\x1b[38;5;3m")
-                .replace("{BLUE}", "\x1b[38;5;12m");
+    let mut escaped = false;
+    let mut i = 1;
+    while let Some(arg) = argv.get(i) {
+        let options = OPTIONS
+            .iter()
+            .chain(parsed.command.map_or(&[][..], |c| c.options));
+
+        if escaped || arg == "-" || !arg.starts_with('-') {
+            match parsed.command {
+                Some(_) => parsed.args.push((i, arg.to_string())),
+                None => parsed.command = Some(command(argv, i)),
+            }
+        } else if arg == "--" {
+            escaped = true;
+        } else if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (long, None),
+            };
+
+            let Some(opt) = options.clone().find(|o| o.long == name) else {
+                let mut err = argv.error("INVALID", "unknown option", i, "unrecognized option");
+                match similar::best(name, options.map(|o| o.long)) {
+                    Some(s) => {
+                        let replacement = match inline {
+                            Some(value) => format!("--{s}={value}"),
+                            None => format!("--{s}"),
+                        };
+                        err.children.push(
+                            argv.suggest("an option with a similar name exists", i, &replacement)
+                                .into(),
+                        );
+                    }
+                    None => err.children.push(usage(parsed.command)),
+                }
+                fail(escape_help(err, &parsed, arg));
+            };
+
+            let at = i;
+            let value = match (opt.value, inline) {
+                (Some(_), Some(value)) => Some((i, value.to_string())),
+                (Some(value), None) => {
+                    i += 1;
+                    let Some(value) = argv.get(i) else {
+                        let mut err = argv.missing(value);
+                        err.children.push(usage(parsed.command));
+                        fail(err);
+                    };
+                    Some((i, value.to_string()))
+                }
+                (None, Some(_)) => {
+                    let mut err = argv.error(
+                        "INVALID",
+                        "unexpected value",
+                        i,
+                        format!("`--{name}` doesn't take a value"),
+                    );
+                    err.children.push(
+                        argv.suggest("remove the value", i, &format!("--{name}"))
+                            .into(),
+                    );
+                    fail(err);
+                }
+                (None, None) => None,
+            };
+
+            parsed.options.push((opt.long, at, value));
+        } else {
+            // short options, which can be bundled, e.g. `-cf json`
+            let shorts = &arg[1..];
+            for (j, c) in shorts.char_indices() {
+                let Some(opt) = options.clone().find(|o| o.short == c) else {
+                    let mut err = argv.error(
+                        "INVALID",
+                        "unknown option",
+                        i,
+                        format!("unrecognized option `-{c}`"),
+                    );
+                    err.children.push(usage(parsed.command));
+                    fail(escape_help(err, &parsed, arg));
+                };
 
-            println!("{help}");
+                let Some(value) = opt.value else {
+                    parsed.options.push((opt.long, i, None));
+                    continue;
+                };
+
+                let at = i;
+                let rest = &shorts[j + c.len_utf8()..];
+                let value = if rest.is_empty() {
+                    i += 1;
+                    let Some(value) = argv.get(i) else {
+                        let mut err = argv.missing(value);
+                        err.children.push(usage(parsed.command));
+                        fail(err);
+                    };
+                    value.to_string()
+                } else {
+                    rest.to_string()
+                };
+
+                parsed.options.push((opt.long, at, Some((i, value))));
+                break;
+            }
         }
 
+        i += 1;
+    }
+
+    parsed
+}
+
+/// Look up the command named by argument `i`, exiting if there's none.
+fn command(argv: &args::Argv, i: usize) -> &'static Command {
+    let name = &argv.args()[i];
+    if let Some(command) = COMMANDS.iter().find(|c| c.name == name) {
+        return command;
+    }
+
+    let mut err = argv.error("INVALID", "invalid command", i, "unrecognized command");
+    match similar::best(name, COMMANDS.iter().map(|c| c.name)) {
+        Some(s) => err.children.push(
+            argv.suggest("a command with a similar name exists", i, s)
+                .into(),
+        ),
+        None => err.children.push(Child::help(format!(
+            "valid commands are {}",
+            list(COMMANDS.iter().map(|c| c.name))
+        ))),
+    }
+    err.children.push(usage(None));
+
+    fail(err)
+}
+
+/// Add a help explaining `--`, if an unknown option might've been meant as
+/// an argument.
+fn escape_help(mut err: Error, parsed: &Parsed, arg: &str) -> Error {
+    if parsed.command.is_some() {
+        err.children.push(Child::help(format!(
+            "to pass `{arg}` as an argument, put `--` before it"
+        )));
+    }
+
+    err
+}
+
+/// A help pointing to the usage of a command, or of frack.
+fn usage(command: Option<&Command>) -> Child {
+    Child::help(match command {
+        Some(c) => format!("try `frack {} --help` for usage", c.name),
+        None => "try `frack help` for usage".to_string(),
+    })
+}
+
+fn main() {
+    let argv = args::Argv::from_env();
+    let parsed = parse(&argv);
+
+    let mut format = Format::Human;
+    for (i, value) in parsed.values("format") {
+        format = Format::parse(value).unwrap_or_else(|| {
+            let mut err = argv.error("INVALID", "invalid format", i, "unrecognized format");
+            match similar::best(value, FORMATS.iter().copied()) {
+                Some(s) => {
+                    let arg = &argv.args()[i];
+                    let replacement = format!("{}{s}", &arg[..arg.len() - value.len()]);
+                    err.children.push(
+                        argv.suggest("a format with a similar name exists", i, &replacement)
+                            .into(),
+                    );
+                }
+                None => err.children.push(Child::help(format!(
+                    "valid formats are {}",
+                    list(FORMATS.iter().copied())
+                ))),
+            }
+
+            fail(err)
+        });
+    }
+
+    if parsed.flag("version") {
+        println!("frack {}", env!("CARGO_PKG_VERSION"));
+        return;
+    }
+
+    if let Some((i, code)) = parsed.values("explain").last() {
+        match CODES.entry(code) {
+            Some(entry) => print!("{}", entry.markdown()),
+            None => fail(CODES.invalid(&argv, i)),
+        }
+        return;
+    }
+
+    if parsed.flag("help") {
+        print_help(parsed.command);
+        return;
+    }
+
+    let Some(command) = parsed.command else {
+        let mut err = argv.missing("command");
+        err.children.push(usage(None));
+        fail(err)
+    };
+
+    if let Some(&(i, _)) = command.max_args.and_then(|max| parsed.args.get(max)) {
+        let mut err = argv.error("INVALID", "unexpected argument", i, "unexpected argument");
+        err.children.push(usage(Some(command)));
+        fail(err)
+    }
+
+    match command.name {
+        "help" => match parsed.args.first() {
+            Some(&(i, _)) => print_help(Some(self::command(&argv, i))),
+            None => print_help(None),
+        },
+
         "example" => {
             let example = include_str!("example.txt")
                 .replace("{OFF}", "\x1b[0m")
@@ -48,293 +530,740 @@ fn main() {
             println!("{example}");
         }
 
-        "error" => {
-            gen(args.peekable(), true);
+        "error" | "warning" => {
+            let at = parsed.values("at").last().map(|(i, s)| (i, s.to_string()));
+            let args = parsed.args.into_iter().peekable();
+            gen(&argv, args, at, command.name == "error", format);
         }
 
-        "warning" => {
-            gen(args.peekable(), false);
-        }
+        "template" => template(&argv, &parsed, format),
+        "render" => render(&argv, &parsed, format),
+        "render-json" => render_json(parsed.flag("check"), format),
+        "fix" => apply_fixes(parsed.flag("dry-run"), parsed.flag("all")),
+        "from-diff" => from_diff(&parsed),
+        "lsp" => lsp(&argv, &parsed),
 
-        other => {
-            let end = other.len();
-            let err = error! {
-                "INVALID", "invalid command";
-                "arg", 1, 1;
-                other;
-                0..=end => "unrecognized command";
-                help "valid commands are `help`, `example`, `error`, `warning`";
-                help "try `frack help` for usage";
-            };
-
-            eprintln!("{err}");
-            std::process::exit(1);
-        }
+        _ => unreachable!(),
     }
 }
 
-fn gen(mut args: Peekable<impl Iterator<Item = String>>, is_error: bool) {
-    let (command, error_code, i) = if is_error {
-        let ec = args.next().unwrap_or_else(|| {
-            let err = error! {
-                "MISSING", "must provide error code";
-                "arg", 1, 2;
-                "error";
-                6..=9 => "no error code provided";
-                help "try `frack help` for usage";
-            };
-
-            eprintln!("{err}");
-            std::process::exit(1);
-        });
+/// Print the help for a command, or for frack as a whole.
+fn print_help(command: Option<&Command>) {
+    let help = match command {
+        Some(c) => {
+            let mut help = format!("{{BOLD}}frack{{OFF}} [options] {}", c.help.trim_start());
+            if matches!(c.name, "error" | "warning") {
+                help.push('\n');
+                help.push_str(include_str!("help/post.txt"));
+            }
+            help.push_str("\n Run {BOLD}frack --help{OFF} for the options every command takes.");
+            help
+        }
 
-        (format!("error '{ec}'"), Some(ec), 3)
-    } else {
-        ("warning".to_string(), None, 2)
+        None => {
+            let commands = COMMANDS.iter().map(|c| c.help).collect::<Vec<_>>();
+            format!(
+                "{}\n {{BOLD}}{{BLUE}}Commands:{{OFF}}\n\n{}\n{}",
+                include_str!("help/options.txt"),
+                commands.join("\n"),
+                include_str!("help/post.txt").trim_end(),
+            )
+        }
     };
 
-    let Some(message) = args.next() else {
-        let start = command.len() + 2;
-        let err = error! {
-            "MISSING", "must provide message";
-            "arg", 1, i;
-            command;
-            start..=start + 3 => "no message provided";
-            help "try `frack help` for usage";
-        };
+    let help = help
+        .replace("{OFF}", "\x1b[0m")
+        .replace("{BOLD}", "\x1b[1m")
+        .replace("{EMPH}", "\x1b[4m")
+        .replace("{RED}", "\x1b[38;5;9m")
+        .replace("{GREEN}", "\x1b[38;5;2m")
+        .replace("{YELLOW}", "\x1b[38;5;3m")
+        .replace("{BLUE}", "\x1b[38;5;12m");
 
-        eprintln!("{err}");
-        std::process::exit(1);
-    };
+    println!("{help}");
+}
 
-    let Some(code) = args.next() else {
-        let msg = format!("{command} '{message}'");
-        let start = msg.len() + 2;
-        let err = error! {
-            "MISSING", "must provide code";
-            "arg", 1, i + 1;
-            msg;
-            start..=start + 3 => "no code provided";
-            help "try `frack help` for usage";
-        };
+/// The subcommands of `error` and `warning`.
+const SUBCOMMANDS: &[&str] = &["at", "line", "mark", "note", "help", "fix"];
 
-        eprintln!("{err}");
-        std::process::exit(1);
+/// The arguments of a command, with their indices in the [`args::Argv`].
+type Args = Peekable<std::vec::IntoIter<(usize, String)>>;
+
+/// Generate an error or warning from the arguments of `frack error` or
+/// `frack warning`, with the span from `--at` if it was given.
+fn gen(
+    argv: &args::Argv,
+    mut args: Args,
+    at: Option<(usize, String)>,
+    is_error: bool,
+    format: Format,
+) {
+    let error_code = is_error.then(|| next(argv, &mut args, "error code").1);
+    let (_, message) = next(argv, &mut args, "message");
+    let (_, code) = next(argv, &mut args, "code");
+    let (start, end) = match at {
+        Some((i, at)) => locate(argv, i, &at, &code),
+        None => span(argv, &mut args, &code),
+    };
+    let label = label(argv, &mut args);
+
+    let mut file = File {
+        path: "src/main.rs".to_string(),
+        line: 7,
+        col: start + 1,
     };
 
-    let (start, end) = span(
-        &mut args,
-        format!("{command} '{message}' '{code}'"),
-        1,
-        i + 2,
+    let mut code = Code::single(
+        code,
+        7,
+        Some(Marker {
+            range: start..=end,
+            symbol: '^',
+            color: if is_error { 9 } else { 3 },
+            message: label,
+            color_span: false,
+        }),
     );
 
-    if is_error {
-        let mut error = error! {
-            error_code.unwrap(), message;
-            "src/main.rs", 7, start + 1;
-            code;
-            start..=end;
-        };
+    let mut children = Vec::new();
 
-        let mut i = 2;
-        while let Some(cmd) = args.next() {
-            match cmd.as_str() {
-                "note" => error.notes.push(note(&mut args, i)),
-                "help" => error.helps.push(help(&mut args, i)),
-                "fix" => error.helps.push(fix(&mut args, i)),
-                other => {
-                    let err = error! {
-                        "INVALID", "invalid subcommand";
-                        "arg", i, 1;
-                        other;
-                        0..=other.len() => "unrecognized subcommand";
-                        help "valid subcommands are `note`, `help`, `fix`";
-                        help "try `frack help` for usage";
-                    };
+    // the help whose suggestion `line` and `mark` apply to, or `None` for the
+    // main code
+    let mut target = None;
 
-                    eprintln!("{err}");
-                    std::process::exit(1);
+    let mut first = true;
+    while let Some((i, cmd)) = args.next() {
+        match cmd.as_str() {
+            "note" => children.push(Child::note(next(argv, &mut args, "note message").1)),
+            "help" => {
+                children.push(Child::help(next(argv, &mut args, "help message").1));
+                target = None;
+            }
+            "fix" => {
+                children.push(fix(argv, &mut args, &code[0].code, file.line).into());
+                target = Some(children.len() - 1);
+            }
+            "at" if first => {
+                file = self::at(argv, &mut args, start + 1);
+                code[0].line_number = file.line;
+            }
+            "line" => {
+                let (code, is_fix) = match target {
+                    Some(h) => (suggestion(&mut children[h]), true),
+                    None => (&mut code, false),
+                };
+                code.push(line(argv, &mut args, is_fix));
+            }
+            "mark" => {
+                let code = match target {
+                    Some(h) => suggestion(&mut children[h]),
+                    None => &mut code,
+                };
+                mark(argv, &mut args, i, code, is_error);
+            }
+            "at" => {
+                let mut err = argv.error(
+                    "INVALID",
+                    "misplaced subcommand",
+                    i,
+                    "`at` must come before the other subcommands",
+                );
+                err.children.push(Child::help(
+                    "the location sets the line numbers of what follows it",
+                ));
+                fail(err)
+            }
+            other => {
+                let mut err = argv.invalid(i, "subcommand", "unrecognized subcommand");
+                match similar::best(other, SUBCOMMANDS.iter().copied()) {
+                    Some(s) => err.children.push(
+                        argv.suggest("a subcommand with a similar name exists", i, s)
+                            .into(),
+                    ),
+                    None => err.children.push(Child::help(format!(
+                        "valid subcommands are {}",
+                        list(SUBCOMMANDS.iter().copied())
+                    ))),
                 }
+                fail(err)
             }
+        }
 
-            i += 1;
+        first = false;
+    }
+
+    code.sort_by_key(|l| l.line_number);
+    for child in &mut children {
+        if let ChildKind::Help(Help {
+            suggestion: Some(s),
+            ..
+        }) = &mut child.kind
+        {
+            s.sort_by_key(|l| l.line_number);
         }
+    }
 
-        println!("{error}");
+    let diag = if is_error {
+        Diagnostic::Error(Error {
+            error_code: error_code.unwrap(),
+            message,
+            file,
+            code,
+            children,
+        })
     } else {
-        let mut warning = warning! {
-            message;
-            "src/main.rs", 7, start + 1;
-            code;
-            start..=end;
+        Diagnostic::Warning(Warning {
+            message,
+            file,
+            code,
+            children,
+        })
+    };
+
+    format.emit(vec![diag]);
+}
+
+/// The suggestion of a `fix` in `gen`.
+fn suggestion(child: &mut Child) -> &mut Code {
+    match &mut child.kind {
+        ChildKind::Help(help) => help.suggestion.as_mut().unwrap(),
+        _ => unreachable!("only fixes are targeted"),
+    }
+}
+
+fn template(argv: &args::Argv, parsed: &Parsed, format: Format) {
+    let Some((i, code)) = parsed.args.first() else {
+        let mut err = argv.missing("template");
+        err.children.push(Child::help(
+            "try `frack template list` for a list of templates",
+        ));
+        fail(err)
+    };
+
+    if code == "list" {
+        if let Some(j) = parsed.index("var") {
+            fail(argv.conflicting(*i, j));
+        }
+
+        for t in template::TEMPLATES {
+            println!("\x1b[1m\x1b[38;5;9m{}\x1b[0m: {}", t.code, t.title);
+            for slot in t.slots {
+                println!("    \x1b[38;5;12m{}\x1b[0m = {:?}", slot.name, slot.default);
+            }
+        }
+
+        return;
+    }
+
+    let Some(t) = template::find(code) else {
+        let mut err = argv.error("INVALID", "invalid template", *i, "unrecognized template");
+        if let Some(s) = similar::best(code, template::TEMPLATES.iter().map(|t| t.code)) {
+            err.children.push(
+                argv.suggest("a template with a similar code exists", *i, s)
+                    .into(),
+            );
+        }
+        err.children.push(Child::help(
+            "try `frack template list` for a list of templates",
+        ));
+        fail(err)
+    };
+
+    let mut vars = template::Vars::new();
+    let mut given = Vec::new();
+    for (j, var) in parsed.values("var") {
+        let Some((name, value)) = var.split_once('=') else {
+            let mut err = argv.error("INVALID", "invalid variable", j, "expected `name=value`");
+            err.children
+                .push(Child::help("variables are given like `--var name=foo`"));
+            fail(err)
         };
 
-        let mut i = 2;
-        while let Some(cmd) = args.next() {
-            match cmd.as_str() {
-                "note" => warning.notes.push(note(&mut args, i)),
-                "help" => warning.helps.push(help(&mut args, i)),
-                "fix" => warning.helps.push(fix(&mut args, i)),
-                other => {
-                    let err = error! {
-                        "INVALID", "invalid subcommand";
-                        "arg", i, 1;
-                        other;
-                        0..=other.len() => "unrecognized subcommand";
-                        help "valid subcommands are `note`, `help`, `fix`";
-                        help "try `frack help` for usage";
-                    };
+        vars = vars.set(name, value);
+        given.push((name, value, j));
+    }
+
+    match t.build(&vars) {
+        // template errors are real `rustc` errors, so they can be explained
+        Ok(error) if matches!(format, Format::Human) => println!("{}", CODES.explained(&error)),
+        Ok(error) => format.emit(vec![error.into()]),
+        Err(e) => {
+            let slot = match &e {
+                template::TemplateError::UnknownSlot(name) => name.as_str(),
+                template::TemplateError::InvalidLine(_) => "line",
+                template::TemplateError::NotFound { slot, .. } => slot,
+            };
+            let var = given.iter().rev().find(|(name, _, _)| *name == slot);
+
+            let mut err = argv.error(
+                "INVALID",
+                format!("invalid variables for template {}", t.code),
+                var.map_or(*i, |&(_, _, j)| j),
+                e.to_string(),
+            );
+
+            let slots = ["path", "line"]
+                .into_iter()
+                .chain(t.slots.iter().map(|s| s.name));
+            let close = var
+                .filter(|_| matches!(e, template::TemplateError::UnknownSlot(_)))
+                .and_then(|&(name, value, j)| {
+                    Some((similar::best(name, slots.clone())?, value, j))
+                });
 
-                    eprintln!("{err}");
-                    std::process::exit(1);
+            match close {
+                Some((s, value, j)) => {
+                    let arg = &argv.args()[j];
+                    let prefix = &arg[..arg.len() - slot.len() - value.len() - 1];
+                    err.children.push(
+                        argv.suggest(
+                            "a slot with a similar name exists",
+                            j,
+                            &format!("{prefix}{s}={value}"),
+                        )
+                        .into(),
+                    );
+                }
+                None => {
+                    err.children
+                        .push(Child::help(format!("`{}` accepts {}", t.code, list(slots))))
                 }
             }
 
-            i += 1;
+            fail(err)
         }
-
-        println!("{warning}");
     }
 }
 
-fn note(args: &mut impl Iterator<Item = String>, major: usize) -> Note {
-    Note(args.next().unwrap_or_else(|| {
-        let err = error! {
-            "MISSING", "must provide note message";
-            "arg", major, 2;
-            "note";
-            6..=9 => "no note message provided";
-            help "try `frack help` for usage";
-        };
+fn render(argv: &args::Argv, parsed: &Parsed, format: Format) {
+    let Some((_, path)) = parsed.args.first() else {
+        let mut err = argv.missing("file");
+        err.children
+            .push(Child::help("pass `-` to read from stdin"));
+        fail(err)
+    };
 
-        eprintln!("{err}");
+    let (name, source) = if path == "-" {
+        let mut source = String::new();
+        let res = std::io::Read::read_to_string(&mut std::io::stdin(), &mut source);
+        ("<stdin>", res.map(|_| source))
+    } else {
+        (path.as_str(), std::fs::read_to_string(path))
+    };
+
+    let source = source.unwrap_or_else(|e| {
+        eprintln!("error: failed to read {name}: {e}");
         std::process::exit(1);
-    }))
+    });
+
+    match frackfile::parse(&source) {
+        Ok(diagnostics) => format.emit(diagnostics),
+        Err(e) => {
+            eprintln!("{}", e.error(name, &source));
+            std::process::exit(1);
+        }
+    }
 }
 
-fn help(args: &mut impl Iterator<Item = String>, major: usize) -> Help {
-    Help {
-        message: args.next().unwrap_or_else(|| {
+/// The `rustc` JSON diagnostics on stdin, exiting if they're invalid.
+fn stdin_messages() -> impl Iterator<Item = json::Message> {
+    json::Stream::new(std::io::stdin().lock()).map(|message| match message {
+        Ok(message) => message,
+        Err(json::StreamError::Parse { line, text, error }) => {
+            let col = text[..error.offset.min(text.len())].chars().count();
             let err = error! {
-                "MISSING", "must provide help message";
-                "arg", major, 2;
-                "help";
-                6..=9 => "no help message provided";
-                help "try `frack help` for usage";
+                "INVALID", "invalid JSON";
+                "<stdin>", line, col + 1;
+                text;
+                col..=col => error.message;
+                help "input must be `rustc --error-format=json` or `cargo --message-format=json` output";
             };
 
             eprintln!("{err}");
             std::process::exit(1);
-        }),
-        suggestion: None,
+        }
+        Err(json::StreamError::Io(e)) => {
+            eprintln!("error: failed to read stdin: {e}");
+            std::process::exit(1);
+        }
+    })
+}
+
+fn render_json(check: bool, format: Format) {
+    let mut mismatches = 0;
+    let mut batch = Vec::new();
+    for message in stdin_messages() {
+        if check {
+            let mismatch = message
+                .rendered
+                .as_deref()
+                .and_then(|r| json::fidelity(&message.diagnostic, r));
+
+            if let Some(m) = mismatch {
+                mismatches += 1;
+                let expected = m.expected.unwrap_or_default();
+                let end = expected.chars().count().max(1) - 1;
+                let warning = warning! {
+                    format!("rendering differs from `rustc` at line {}", m.line);
+                    "<rendered>", m.line, 1;
+                    expected;
+                    0..=end => "`rustc` rendered this";
+                    note format!("frack rendered: {:?}", m.found.unwrap_or_default());
+                };
+
+                eprintln!("{warning}");
+            }
+        }
+
+        if format.streams() {
+            format.emit(vec![message.diagnostic]);
+        } else {
+            batch.push(message.diagnostic);
+        }
+    }
+
+    if !format.streams() {
+        format.emit(batch);
+    }
+
+    if mismatches != 0 {
+        std::process::exit(1);
     }
 }
 
-fn fix(args: &mut Peekable<impl Iterator<Item = String>>, major: usize) -> Help {
-    let Some(message) = args.next() else {
-        let err = error! {
-            "MISSING", "must provide help message";
-            "arg", major, 2;
-            "fix";
-            5..=8 => "no help message provided";
-            help "try `frack help` for usage";
+fn apply_fixes(dry_run: bool, all: bool) {
+    let diagnostics: Vec<_> = stdin_messages().map(|m| m.diagnostic).collect();
+    let fixes: Vec<_> = fix::fixes(&diagnostics)
+        .into_iter()
+        .filter(|f| !f.path.is_empty())
+        .filter(|f| all || f.applicability == Applicability::MachineApplicable)
+        .collect();
+
+    let mut paths: Vec<&str> = Vec::new();
+    for fix in &fixes {
+        if !paths.contains(&fix.path.as_str()) {
+            paths.push(&fix.path);
+        }
+    }
+
+    let mut failed = false;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("error: failed to read {path}: {e}");
+                failed = true;
+                continue;
+            }
         };
 
-        eprintln!("{err}");
+        let applied = fix::apply(&source, fixes.iter().filter(|f| f.path == path));
+        for (fix, err) in &applied.skipped {
+            let (fix::FixError::Mismatch { line } | fix::FixError::Conflict { line }) = *err;
+            let warning = Warning {
+                message: format!("skipped fix: {}", fix.message),
+                file: File {
+                    path: path.to_string(),
+                    line,
+                    col: 1,
+                },
+                code: Code(Vec::new()),
+                children: vec![Child::note(err)],
+            };
+            eprintln!("{warning}");
+        }
+
+        if applied.source == source {
+            continue;
+        }
+
+        if dry_run {
+            print!(
+                "{}",
+                patch::diff(path, &source, &applied.source, patch::CONTEXT)
+            );
+            continue;
+        }
+
+        if let Err(e) = std::fs::write(path, &applied.source) {
+            eprintln!("error: failed to write {path}: {e}");
+            failed = true;
+            continue;
+        }
+
+        let count = applied.applied.len();
+        let plural = if count == 1 { "fix" } else { "fixes" };
+        eprintln!("Fixed {path} ({count} {plural})");
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn from_diff(parsed: &Parsed) {
+    let message = parsed
+        .values("message")
+        .last()
+        .map_or("consider making this change", |(_, m)| m);
+
+    let mut diff = String::new();
+    if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut diff) {
+        eprintln!("error: failed to read stdin: {e}");
         std::process::exit(1);
+    }
+
+    match patch::parse(&diff, message) {
+        Ok(hunks) => hunks.iter().for_each(|h| print!("{h}")),
+        Err(e) => {
+            eprintln!("{}", e.error("<stdin>", &diff));
+            std::process::exit(1);
+        }
+    }
+}
+
+fn lsp(argv: &args::Argv, parsed: &Parsed) {
+    let Some((_, path)) = parsed.args.first() else {
+        let mut err = argv.missing("diagnostics file");
+        err.children.push(Child::help(
+            "the file should contain `rustc --error-format=json` output",
+        ));
+        fail(err)
     };
 
-    let Some(code) = args.next() else {
-        let msg = format!("fix '{message}'");
-        let start = msg.len() + 2;
-        let err = error! {
-            "MISSING", "must provide help code";
-            "arg", major, 3;
-            msg;
-            start..=start + 3 => "no help code provided";
-            help "try `frack help` for usage";
+    let mut server = lsp::Server::new(path);
+    for (i, poll) in parsed.values("poll") {
+        let Ok(ms) = poll.parse() else {
+            fail(argv.error("INVALID", "invalid interval", i, "expected milliseconds"))
         };
+        server = server.poll_interval(std::time::Duration::from_millis(ms));
+    }
 
-        eprintln!("{err}");
+    let stdin = std::io::BufReader::new(std::io::stdin());
+    if let Err(e) = server.run(stdin, std::io::stdout().lock()) {
+        eprintln!("error: language server failed: {e}");
         std::process::exit(1);
-    };
+    }
+}
 
-    let (start, end) = span(args, format!("fix '{message}' '{code}'"), major, 4);
+/// The next argument, exiting if there's none.
+fn next(argv: &args::Argv, args: &mut Args, what: &str) -> (usize, String) {
+    args.next().unwrap_or_else(|| {
+        let mut err = argv.missing(what);
+        err.children.push(usage(None));
+        fail(err)
+    })
+}
 
-    let next = args.peek().map(String::as_str) == Some("span");
-    let marker_message = if next {
-        Some(args.nth(1).unwrap_or_else(|| {
-            let msg = format!("fix '{message}' '{code}' {start}-{end} span");
-            let start = msg.len() + 2;
-            let err = error! {
-                "MISSING", "must provide span message";
-                "arg", major, 6;
-                msg;
-                start..=start + 3 => "no span message provided";
-                help "if you don't want a span message, omit `span`";
-                help "try `frack help` for usage";
-            };
+fn fix(argv: &args::Argv, args: &mut Args, original: &str, line: usize) -> Help {
+    let (_, message) = next(argv, args, "help message");
 
-            eprintln!("{err}");
-            std::process::exit(1);
-        }))
-    } else {
-        None
-    };
+    // the suggestion is given by `line` subcommands instead
+    if args.peek().is_some_and(|(_, a)| a == "line") {
+        return Help {
+            message,
+            suggestion: Some(Code(Vec::new())),
+            style: SuggestionStyle::Verbose,
+            applicability: Applicability::Unspecified,
+        };
+    }
+
+    let (_, code) = next(argv, args, "help code");
+
+    // mark what changed from the original
+    if args.next_if(|(_, a)| a == "auto").is_some() {
+        let mut suggestion = Code::suggestion(original, &code, line);
+        if let Some(marker) = suggestion.last_mut().and_then(|l| l.marker.as_mut()) {
+            marker.message = label(argv, args);
+        }
+
+        return Help {
+            message,
+            suggestion: Some(suggestion),
+            style: SuggestionStyle::Verbose,
+            applicability: Applicability::Unspecified,
+        };
+    }
+
+    let (start, end) = span(argv, args, &code);
+    let marker_message = label(argv, args);
 
     Help {
         message,
         suggestion: Some(Code::single(
             code,
-            7,
+            line,
             Some(Marker {
                 range: start..=end,
                 symbol: '~',
-                color: 2,
+                color: 10,
                 message: marker_message,
                 color_span: true,
             }),
         )),
+        style: SuggestionStyle::Verbose,
+        applicability: Applicability::Unspecified,
     }
 }
 
-fn span(
-    args: &mut impl Iterator<Item = String>,
-    command: String,
-    major: usize,
-    minor: usize,
-) -> (usize, usize) {
-    let Some(span) = args.next() else {
-        let start = command.len() + 2;
-        let err = error! {
-            "MISSING", "must provide span";
-            "arg", major, minor;
-            command;
-            start..=start + 3 => "no span provided";
-            help "try `frack help` for usage";
-        };
+/// Parse the `<path>:<line>[:<col>]` of an `at` subcommand.
+fn at(argv: &args::Argv, args: &mut Args, col: usize) -> File {
+    let (i, location) = next(argv, args, "location");
 
-        eprintln!("{err}");
-        std::process::exit(1);
+    let mut parts = location.rsplitn(3, ':').collect::<Vec<_>>();
+    parts.reverse();
+    let numbers = parts[1..]
+        .iter()
+        .map(|n| n.parse::<usize>().ok().filter(|&n| n != 0))
+        .collect::<Option<Vec<_>>>();
+
+    let file = match (parts.as_slice(), numbers.as_deref()) {
+        ([path, _, _], Some(&[line, col])) if !path.is_empty() => Some((path, line, col)),
+        ([path, _], Some(&[line])) if !path.is_empty() => Some((path, line, col)),
+        _ => None,
     };
 
-    span.split_once('-')
-        .and_then(|(s, e)| Some((s.parse().ok()?, e.parse().ok()?)))
-        .unwrap_or_else(|| {
-            let msg = format!("{command} {span}");
-            let start = command.len() + 2;
-            let end = msg.len() + 1;
-            let err = error! {
-                "INVALID", "invalid span";
-                "arg", 1, 5;
-                msg;
-                start..=end => "invalid span";
-                help "span must be in the format `start-end`, e.g. `3-15`";
-                help "try `frack help` for usage";
-            };
+    let Some((path, line, col)) = file else {
+        let mut err = argv.invalid(i, "location", "expected `<path>:<line>[:<col>]`");
+        err.children.push(Child::help(
+            "locations look like `src/lib.rs:12:5`; the column is optional",
+        ));
+        err.children
+            .push(Child::note("line and column numbers start at 1"));
+        fail(err)
+    };
 
-            eprintln!("{err}");
-            std::process::exit(1);
-        })
+    File {
+        path: path.to_string(),
+        line,
+        col,
+    }
+}
+
+/// Parse a `line <number> <code> [<span> [span <message>]]` subcommand.
+fn line(argv: &args::Argv, args: &mut Args, is_fix: bool) -> Line {
+    let (i, number) = next(argv, args, "line number");
+    let Some(line_number) = number.parse::<usize>().ok().filter(|&n| n != 0) else {
+        fail(argv.invalid(i, "line number", "line numbers start at 1"))
+    };
+
+    let (_, code) = next(argv, args, "code");
+
+    // subcommands are words, so anything else is a span
+    let is_span = args.peek().is_some_and(|(_, s)| {
+        s.starts_with(|c: char| matches!(c, '`' | '/') || c.is_ascii_digit())
+    });
+
+    let marker = is_span.then(|| {
+        let (start, end) = span(argv, args, &code);
+        let message = label(argv, args);
+        let (symbol, color) = if is_fix { ('~', 10) } else { ('-', 12) };
+
+        Marker {
+            range: start..=end,
+            symbol,
+            color,
+            message,
+            color_span: is_fix,
+        }
+    });
+
+    Line {
+        code,
+        line_number,
+        marker,
+    }
+}
+
+/// Parse a `mark <symbol> [<color>]` subcommand at argument `i`, restyling
+/// the last marker in `code`.
+fn mark(argv: &args::Argv, args: &mut Args, i: usize, code: &mut Code, is_error: bool) {
+    let (j, symbol) = next(argv, args, "symbol");
+    let mut chars = symbol.chars();
+    let (Some(symbol), None) = (chars.next(), chars.next()) else {
+        let mut err = argv.invalid(j, "symbol", "must be a single character");
+        err.children.push(Child::help(
+            "`rustc` uses `^` for primary spans, `-` for secondary spans, and `~` or `+` for fixes",
+        ));
+        fail(err)
+    };
+
+    let color = args.next_if(|(_, c)| c.parse::<u8>().is_ok());
+    let color = color
+        .and_then(|(_, c)| c.parse().ok())
+        .unwrap_or(match symbol {
+            '^' if is_error => 9,
+            '^' => 3,
+            '~' | '+' => 10,
+            _ => 12,
+        });
+
+    let Some(marker) = code.iter_mut().rev().find_map(|l| l.marker.as_mut()) else {
+        let mut err = argv.error("INVALID", "nothing to mark", i, "no span to restyle");
+        err.children.push(Child::help(
+            "`mark` restyles the span of the preceding `line` or `fix`",
+        ));
+        fail(err)
+    };
+
+    marker.symbol = symbol;
+    marker.color = color;
+    marker.color_span = matches!(symbol, '~' | '+');
+}
+
+/// Parse an optional `span <message>` after a span.
+fn label(argv: &args::Argv, args: &mut Args) -> Option<String> {
+    args.next_if(|(_, a)| a == "span")?;
+    Some(next(argv, args, "span message").1)
+}
+
+/// Parse a span, and locate it in `code`.
+fn span(argv: &args::Argv, args: &mut Args, code: &str) -> (usize, usize) {
+    let (i, span) = next(argv, args, "span");
+    locate(argv, i, &span, code)
+}
+
+/// Locate the span given as argument `i` in `code`.
+fn locate(argv: &args::Argv, i: usize, span: &str, code: &str) -> (usize, usize) {
+    use locate::{Locate, LocateError};
+
+    let err = match span.locate(code) {
+        Ok(range) => return (*range.start(), *range.end()),
+        Err(err) => err,
+    };
+
+    let mut err = match err {
+        LocateError::Syntax(_) => argv.invalid(i, "span", "expected `start-end`"),
+        err @ (LocateError::Regex(_)
+        | LocateError::Reversed { .. }
+        | LocateError::ZerothOccurrence { .. }
+        | LocateError::TooComplex { .. }) => argv.invalid(i, "span", err),
+        err => {
+            let mut e = argv.error("INVALID", "span not found", i, err);
+            e.children
+                .push(Child::note(format!("the code is `{code}`")));
+            fail(e)
+        }
+    };
+
+    err.children.push(Child::help(
+        "spans look like `3-15`, `3-` for the rest of the line, `` `text` ``, or `/regex/`",
+    ));
+    err.children.push(Child::note(
+        "follow text or a regex with `#n` to point at its nth occurrence, e.g. `` `x`#2 ``",
+    ));
+    fail(err)
+}
+
+fn fail(err: Error) -> ! {
+    eprintln!("{err}");
+    std::process::exit(1);
+}
+
+/// A list of names in backticks, e.g. "`a`, `b`, `c`".
+fn list<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names
+        .map(|n| format!("`{n}`"))
+        .collect::<Vec<_>>()
+        .join(", ")
 }
@@ -0,0 +1,258 @@
+//! A tiny Markdown renderer for long-form error explanations.
+//!
+//! Only the subset of Markdown used by `rustc`'s error explanations is
+//! supported: ATX headings, paragraphs, fenced code blocks, bullet and
+//! numbered lists, and inline code, emphasis, strong emphasis, and links.
+
+use std::fmt;
+
+use crate::{bold, color, normal};
+
+/// Markdown text, rendered for the terminal.
+///
+/// To display using ANSI escape codes, use the [`Display`](fmt::Display) impl.
+pub struct Markdown<'a>(pub &'a str);
+
+impl fmt::Display for Markdown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let blocks = parse(self.0);
+        for (i, block) in blocks.iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+
+            match block {
+                Block::Heading(level, text) => {
+                    bold(f)?;
+                    if *level == 1 {
+                        write!(f, "\x1b[4m")?;
+                    }
+                    inline(text, true, f)?;
+                    normal(f)?;
+                    writeln!(f)?;
+                }
+
+                Block::Paragraph(text) => {
+                    inline(text, false, f)?;
+                    writeln!(f)?;
+                }
+
+                Block::Fence(_, code) => {
+                    for line in code.lines() {
+                        color(12, f)?;
+                        write!(f, "    {line}")?;
+                        normal(f)?;
+                        writeln!(f)?;
+                    }
+                }
+
+                Block::List(items) => {
+                    for (n, item) in items {
+                        match n {
+                            Some(n) => write!(f, "{n}. ")?,
+                            None => write!(f, "* ")?,
+                        }
+                        inline(item, false, f)?;
+                        writeln!(f)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Fence(String, String),
+    List(Vec<(Option<usize>, String)>),
+}
+
+pub(crate) fn parse(md: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = md.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        if let Some(lang) = trimmed.strip_prefix("```") {
+            let mut code = String::new();
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with("```") {
+                    break;
+                }
+                code.push_str(line);
+                code.push('\n');
+            }
+
+            blocks.push(Block::Fence(lang.trim().to_string(), code));
+            continue;
+        }
+
+        if let Some(heading) = heading(trimmed) {
+            blocks.push(heading);
+            continue;
+        }
+
+        if list_item(trimmed).is_some() {
+            let mut items = Vec::new();
+            let mut line = Some(line);
+            while let Some(l) = line {
+                let l = l.trim();
+                if let Some(item) = list_item(l) {
+                    items.push((item.0, item.1.to_string()));
+                } else if let Some((_, text)) = items.last_mut() {
+                    text.push(' ');
+                    text.push_str(l);
+                }
+
+                line = lines.next_if(|l| {
+                    let l = l.trim();
+                    !l.is_empty() && !l.starts_with("```") && heading(l).is_none()
+                });
+            }
+
+            blocks.push(Block::List(items));
+            continue;
+        }
+
+        let mut text = trimmed.to_string();
+        while let Some(l) = lines.next_if(|l| {
+            let l = l.trim();
+            !l.is_empty() && !l.starts_with("```") && heading(l).is_none() && list_item(l).is_none()
+        }) {
+            text.push(' ');
+            text.push_str(l.trim());
+        }
+
+        blocks.push(Block::Paragraph(text));
+    }
+
+    blocks
+}
+
+fn heading(line: &str) -> Option<Block> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| Block::Heading(level, text.trim().to_string()))
+}
+
+fn list_item(line: &str) -> Option<(Option<usize>, &str)> {
+    if let Some(item) = line.strip_prefix("* ").or_else(|| line.strip_prefix("- ")) {
+        return Some((None, item));
+    }
+
+    let (n, item) = line.split_once(". ")?;
+    Some((Some(n.parse().ok()?), item))
+}
+
+/// A piece of inline Markdown.
+pub(crate) enum Span<'a> {
+    Text(&'a str),
+    Code(&'a str),
+    Strong(&'a str),
+    Emph(&'a str),
+    Link(&'a str, &'a str),
+}
+
+pub(crate) fn spans(text: &str) -> Vec<Span<'_>> {
+    let mut out = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let Some(i) = rest.find(['`', '*', '_', '[']) else {
+            out.push(Span::Text(rest));
+            break;
+        };
+
+        let (before, after) = rest.split_at(i);
+        let parsed = if let Some(after) = after.strip_prefix('`') {
+            after
+                .find('`')
+                .map(|end| (Span::Code(&after[..end]), &after[end + 1..]))
+        } else if let Some(after) = after.strip_prefix("**") {
+            after
+                .find("**")
+                .map(|end| (Span::Strong(&after[..end]), &after[end + 2..]))
+        } else if let Some(after) = after.strip_prefix('[') {
+            after.find("](").and_then(|mid| {
+                let end = after[mid..].find(')')? + mid;
+                Some((
+                    Span::Link(&after[..mid], &after[mid + 2..end]),
+                    &after[end + 1..],
+                ))
+            })
+        } else {
+            // `*emph*` or `_emph_`, but not `snake_case`
+            let delim = &after[..1];
+            let word_start = before.chars().last().is_none_or(|c| !c.is_alphanumeric());
+            let after = &after[1..];
+            after
+                .find(delim)
+                .filter(|_| word_start)
+                .map(|end| (Span::Emph(&after[..end]), &after[end + 1..]))
+        };
+
+        match parsed {
+            Some((span, after)) => {
+                if !before.is_empty() {
+                    out.push(Span::Text(before));
+                }
+                out.push(span);
+                rest = after;
+            }
+            None => {
+                let split = i + 1;
+                out.push(Span::Text(&rest[..split]));
+                rest = &rest[split..];
+            }
+        }
+    }
+
+    out
+}
+
+fn inline(text: &str, heading: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let restore = |f: &mut fmt::Formatter<'_>| {
+        normal(f)?;
+        if heading {
+            bold(f)?;
+        }
+        Ok(())
+    };
+
+    for span in spans(text) {
+        match span {
+            Span::Text(t) => write!(f, "{t}")?,
+            Span::Code(c) => {
+                color(12, f)?;
+                write!(f, "{c}")?;
+                restore(f)?;
+            }
+            Span::Strong(s) => {
+                bold(f)?;
+                write!(f, "{s}")?;
+                restore(f)?;
+            }
+            Span::Emph(e) => {
+                write!(f, "\x1b[3m{e}")?;
+                restore(f)?;
+            }
+            Span::Link(t, url) => {
+                write!(f, "\x1b[4m{t}")?;
+                restore(f)?;
+                write!(f, " <{url}>")?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,574 @@
+//! Converting between suggestions and unified diffs.
+//!
+//! [`patch`] exports suggestions as a diff for `git apply` or `patch`:
+//!
+//! ```rust,no_run
+//! use frack::{fix, json, patch};
+//!
+//! let input = std::io::stdin().lock();
+//! let diagnostics: Vec<_> = json::Stream::new(input)
+//!     .filter_map(Result::ok)
+//!     .map(|m| m.diagnostic)
+//!     .collect();
+//!
+//! let fixes = fix::fixes(&diagnostics);
+//! let patch = patch::patch(&fixes, |path| std::fs::read_to_string(path)).unwrap();
+//! print!("{patch}");
+//! ```
+//!
+//! ```text
+//! --- a/src/main.rs
+//! +++ b/src/main.rs
+//! @@ -1,4 +1,4 @@
+//!  fn main() {
+//! -    let x: i32 = "hello";
+//! +    let x: i32 = 5;
+//!      println!("{x}");
+//!  }
+//! ```
+//!
+//! [`parse`] goes the other way, turning each hunk of a diff into a help, as
+//! if `rustc` had suggested it:
+//!
+//! ```text
+//! help: consider making this change
+//!  --> src/main.rs:2:18
+//!   |
+//! 2 |     let x: i32 = 5;
+//!   |                  ~
+//! ```
+
+use std::fmt::{self, Write};
+use std::io;
+use std::ops::Range;
+
+use crate::fix::{self, Fix};
+use crate::frackfile::ParseError;
+use crate::{
+    bold, color, edit, normal, Applicability, Code, File, Help, Line, Marker, SuggestionStyle,
+};
+
+/// How many unchanged lines to show around changes, like `diff -u`.
+pub const CONTEXT: usize = 3;
+
+/// A patch making every one of `fixes`, with `source` reading each file.
+///
+/// Fixes are applied per file like [`fix::apply`], so ones that conflict or
+/// don't match the file are left out. Files are in the order their first fix
+/// appears.
+pub fn patch(
+    fixes: &[Fix],
+    mut source: impl FnMut(&str) -> io::Result<String>,
+) -> io::Result<String> {
+    let mut paths: Vec<&str> = Vec::new();
+    for fix in fixes.iter().filter(|f| !f.path.is_empty()) {
+        if !paths.contains(&fix.path.as_str()) {
+            paths.push(&fix.path);
+        }
+    }
+
+    let mut patch = String::new();
+    for path in paths {
+        let old = source(path)?;
+        let new = fix::apply(&old, fixes.iter().filter(|f| f.path == path)).source;
+        patch.push_str(&diff(path, &old, &new, CONTEXT));
+    }
+
+    Ok(patch)
+}
+
+/// What happened to a line between the old and new text.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// A unified diff from `old` to `new`, showing `context` unchanged lines
+/// around each change. The files are named `a/path` and `b/path`, like `git`.
+///
+/// Empty if the texts are the same.
+pub fn diff(path: &str, old: &str, new: &str, context: usize) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let ops = ops(&old, &new);
+
+    let changes: Vec<usize> = (0..ops.len()).filter(|&i| ops[i].0 != Op::Same).collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // the number of old and new lines before each op
+    let mut before = Vec::with_capacity(ops.len() + 1);
+    let (mut a, mut b) = (0, 0);
+    for (op, _) in &ops {
+        before.push((a, b));
+        a += (*op != Op::Added) as usize;
+        b += (*op != Op::Removed) as usize;
+    }
+    before.push((a, b));
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- a/{path}\n+++ b/{path}");
+
+    let mut i = 0;
+    while i < changes.len() {
+        // changes separated by at most twice the context share a hunk
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= 2 * context + 1 {
+            j += 1;
+        }
+
+        let start = changes[i].saturating_sub(context);
+        let end = (changes[j] + context + 1).min(ops.len());
+        let (a, b) = before[start];
+        let (a_end, b_end) = before[end];
+
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            range(a, a_end - a),
+            range(b, b_end - b)
+        );
+
+        for &(op, line) in &ops[start..end] {
+            let prefix = match op {
+                Op::Same => ' ',
+                Op::Removed => '-',
+                Op::Added => '+',
+            };
+
+            out.push(prefix);
+            out.push_str(line);
+            if !line.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        i = j + 1;
+    }
+
+    out
+}
+
+/// A hunk header range starting after line `before`, e.g. `3,4`.
+fn range(before: usize, count: usize) -> String {
+    match count {
+        // an empty range names the line before it
+        0 => format!("{before},0"),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{count}", before + 1),
+    }
+}
+
+/// The lines of `old` and `new`, in order, marked by whether they were
+/// removed, added, or kept.
+fn ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // longest common subsequence lengths of the suffixes of `a` and `b`
+    let width = b.len() + 1;
+    let mut lcs = vec![0; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut ops: Vec<_> = old[..prefix].iter().map(|&l| (Op::Same, l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((Op::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len()
+            || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            ops.push((Op::Removed, a[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Added, b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[old.len() - suffix..].iter().map(|&l| (Op::Same, l)));
+
+    ops
+}
+
+/// A hunk of a diff, as a [`Help`] suggesting its changes.
+pub struct Hunk {
+    /// Where the first change is, in the new file.
+    pub file: File,
+
+    /// The suggestion, showing each changed line once.
+    pub help: Help,
+}
+
+impl fmt::Display for Hunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        bold(f)?;
+        color(14, f)?;
+        write!(f, "help")?;
+        normal(f)?;
+        writeln!(f, ": {}", self.help.message)?;
+
+        let code = self.help.suggestion.as_ref();
+        self.file
+            .display(code.map_or(1, Code::line_number_width), f)?;
+        match code {
+            Some(code) => code.display(true, f),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parse the hunks of a unified diff, such as `git diff` output, each as a
+/// help with `message`.
+///
+/// Changed lines are shown like [`Code::suggestion`] shows them: as they are
+/// in the new file, with `~` under what was replaced and `+` under what was
+/// inserted. Lines that were removed entirely, or only had text removed, are
+/// shown as they were, with `-` under what was removed.
+///
+/// Anything outside of a file's `---`/`+++` header and its hunks, like `git`'s
+/// `diff --git` and `index` lines, is ignored.
+pub fn parse(patch: &str, message: &str) -> Result<Vec<Hunk>, ParseError> {
+    let err = |line: usize, text: &str, message: &str, label: &str| ParseError {
+        line: line + 1,
+        range: 0..=text.chars().count().max(1) - 1,
+        message: message.to_string(),
+        label: label.to_string(),
+        help: None,
+    };
+
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut hunks = Vec::new();
+    let mut path: Option<String> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(old) = line.strip_prefix("--- ") {
+            let Some(new) = lines.get(i + 1).and_then(|l| l.strip_prefix("+++ ")) else {
+                return Err(err(
+                    i,
+                    line,
+                    "invalid diff header",
+                    "expected a `+++` line after this",
+                ));
+            };
+
+            let name = |p: &str| p.split('\t').next().unwrap_or("").trim().to_string();
+            let (old, new) = (name(old), name(new));
+            path = Some(match new.as_str() {
+                "/dev/null" => old.strip_prefix("a/").unwrap_or(&old).to_string(),
+                _ => new.strip_prefix("b/").unwrap_or(&new).to_string(),
+            });
+            i += 2;
+            continue;
+        }
+
+        if !line.starts_with("@@ ") {
+            i += 1;
+            continue;
+        }
+
+        let Some(path) = &path else {
+            return Err(err(
+                i,
+                line,
+                "hunk outside of a file",
+                "expected `---` and `+++` lines before this",
+            ));
+        };
+        let Some((old, new)) = header(line) else {
+            return Err(err(
+                i,
+                line,
+                "invalid hunk header",
+                "expected `@@ -<line>,<count> +<line>,<count> @@`",
+            ));
+        };
+
+        let (mut removed, mut added) = (Vec::new(), Vec::new());
+        let (mut old_left, mut new_left) = (old.1, new.1);
+        let mut number = new.0.max(1);
+        let mut changed = Vec::new();
+        i += 1;
+        while old_left > 0 || new_left > 0 {
+            let Some(&line) = lines.get(i) else {
+                return Err(err(i - 1, lines[i - 1], "invalid hunk", "hunk ends early"));
+            };
+
+            match line.chars().next() {
+                Some('-') if old_left > 0 => {
+                    removed.push(&line[1..]);
+                    old_left -= 1;
+                }
+                Some('+') if new_left > 0 => {
+                    added.push(&line[1..]);
+                    new_left -= 1;
+                }
+                Some('\\') => {}
+                // some tools strip the space from empty context lines
+                Some(' ') | None if old_left > 0 && new_left > 0 => {
+                    number = changes(&mut changed, &mut removed, &mut added, number) + 1;
+                    old_left -= 1;
+                    new_left -= 1;
+                }
+                _ => {
+                    return Err(err(
+                        i,
+                        line,
+                        "invalid hunk",
+                        "line doesn't fit the hunk header",
+                    ))
+                }
+            }
+            i += 1;
+        }
+        changes(&mut changed, &mut removed, &mut added, number);
+
+        let Some(first) = changed.first() else {
+            continue;
+        };
+
+        hunks.push(Hunk {
+            file: File {
+                path: path.clone(),
+                line: first.line_number,
+                col: first.marker.as_ref().map_or(0, |m| *m.range.start()) + 1,
+            },
+            help: Help {
+                message: message.to_string(),
+                suggestion: Some(Code(changed)),
+                style: SuggestionStyle::Verbose,
+                applicability: Applicability::Unspecified,
+            },
+        });
+    }
+
+    Ok(hunks)
+}
+
+/// The old and new `(line, count)` of a hunk header, e.g.
+/// `@@ -3,4 +3,5 @@ fn main() {`.
+fn header(line: &str) -> Option<((usize, usize), (usize, usize))> {
+    let mut parts = line.strip_prefix("@@ ")?.split(' ');
+    let range = |part: Option<&str>, sign: char| {
+        let part = part?.strip_prefix(sign)?;
+        match part.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((part.parse().ok()?, 1)),
+        }
+    };
+
+    let old = range(parts.next(), '-')?;
+    let new = range(parts.next(), '+')?;
+    (parts.next()? == "@@").then_some((old, new))
+}
+
+/// Turn a block of `removed` lines followed by `added` lines, starting at new
+/// line `number`, into marked lines, pairing them up in order. Returns the
+/// new line number after the block.
+fn changes(
+    changed: &mut Vec<Line>,
+    removed: &mut Vec<&str>,
+    added: &mut Vec<&str>,
+    mut number: usize,
+) -> usize {
+    let line = |code: &str, number: usize, range: Range<usize>, symbol: char| {
+        // marker ranges are in characters
+        let start = edit::column(code, range.start);
+        let len = code[range].chars().count();
+        Line {
+            code: code.to_string(),
+            line_number: number,
+            marker: Some(Marker {
+                range: start..=start + len.max(1) - 1,
+                symbol,
+                color: if symbol == '-' { 9 } else { 10 },
+                message: None,
+                color_span: true,
+            }),
+        }
+    };
+    // the whole line, less indentation
+    let whole = |code: &str| code.len() - code.trim_start().len()..code.len();
+
+    let pairs = removed.len().min(added.len());
+    for (old, new) in removed.iter().zip(added.iter()) {
+        if old != new {
+            changed.extend(Code::suggestion(old, new, number).0);
+        }
+        number += 1;
+    }
+
+    for old in &removed[pairs..] {
+        changed.push(line(old, number, whole(old), '-'));
+    }
+    for new in &added[pairs..] {
+        changed.push(line(new, number, whole(new), '+'));
+        number += 1;
+    }
+
+    removed.clear();
+    added.clear();
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, parse, CONTEXT};
+    use crate::fix::{self, Change, Fix};
+    use crate::{edit, Applicability};
+
+    /// `1\n2\n...\nn\n`, with the lines in `changes` replaced.
+    fn numbers(n: usize, changes: &[(usize, &str)]) -> String {
+        (1..=n)
+            .map(|i| match changes.iter().find(|(line, _)| *line == i) {
+                Some((_, text)) => format!("{text}\n"),
+                None => format!("{i}\n"),
+            })
+            .collect()
+    }
+
+    fn headers(diff: &str) -> Vec<&str> {
+        diff.lines().filter(|l| l.starts_with("@@")).collect()
+    }
+
+    #[test]
+    fn hunk_headers_match_diff_u() {
+        let old = numbers(10, &[]);
+        let new = numbers(10, &[(5, "five")]);
+        assert_eq!(
+            headers(&diff("f", &old, &new, CONTEXT)),
+            ["@@ -2,7 +2,7 @@"]
+        );
+
+        // changes up to twice the context apart share a hunk
+        let new = numbers(10, &[(2, "two"), (9, "nine")]);
+        assert_eq!(
+            headers(&diff("f", &old, &new, CONTEXT)),
+            ["@@ -1,10 +1,10 @@"]
+        );
+
+        let old = numbers(20, &[]);
+        let new = numbers(20, &[(2, "two"), (19, "nineteen")]);
+        assert_eq!(
+            headers(&diff("f", &old, &new, CONTEXT)),
+            ["@@ -1,5 +1,5 @@", "@@ -16,5 +16,5 @@"]
+        );
+
+        // an empty range names the line before it
+        assert_eq!(headers(&diff("f", "a\n", "x\na\n", 0)), ["@@ -0,0 +1 @@"]);
+        assert_eq!(
+            headers(&diff("f", "a\n", "x\na\n", CONTEXT)),
+            ["@@ -1 +1,2 @@"]
+        );
+        assert_eq!(
+            headers(&diff("f", "a\nb\n", "a\n", CONTEXT)),
+            ["@@ -1,2 +1 @@"]
+        );
+
+        assert_eq!(diff("f", "a\n", "a\n", CONTEXT), "");
+    }
+
+    #[test]
+    fn missing_newlines_are_marked() {
+        assert_eq!(
+            diff("f", "a\nb", "a\nc", CONTEXT),
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n\
+             +c\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn parsed_hunks_make_the_same_changes() {
+        let old = "fn main() {\n    let x = 1;\n    let y = x;\n}\n";
+        for new in [
+            "fn main() {\n    let mut x = 1u8;\n    let y = x;\n}\n",
+            "fn main() {\n    let x = 1;\n    let y = x.clone();\n}\n",
+            "fn main() {\n    let x = 2;\n    let y = x + 1;\n}\n",
+            "fn main() {\n    let x = 1;\n    let y = x;\n}\n",
+        ] {
+            let hunks = parse(&diff("src/main.rs", old, new, CONTEXT), "change").unwrap();
+
+            // each changed line of the new file is shown once, in order
+            let fixes: Vec<_> = hunks
+                .iter()
+                .map(|hunk| {
+                    assert_eq!(hunk.file.path, "src/main.rs");
+                    let suggestion = hunk.help.suggestion.as_ref().unwrap();
+                    let first = &suggestion[0];
+                    assert_eq!(hunk.file.line, first.line_number);
+                    Fix {
+                        path: hunk.file.path.clone(),
+                        message: hunk.help.message.clone(),
+                        applicability: Applicability::Unspecified,
+                        changes: suggestion
+                            .iter()
+                            .map(|line| Change {
+                                line_number: line.line_number,
+                                original: None,
+                                code: edit::revised(line).into_owned(),
+                            })
+                            .collect(),
+                    }
+                })
+                .collect();
+
+            assert_eq!(fix::apply(old, &fixes).source, new, "{new}");
+        }
+    }
+
+    #[test]
+    fn removed_and_added_lines() {
+        let old = "a\nb\nc\n";
+        let hunks = parse(&diff("f", old, "a\nc\nd\n", CONTEXT), "change").unwrap();
+        assert_eq!(hunks.len(), 1);
+
+        let lines = hunks[0].help.suggestion.as_ref().unwrap();
+        let shown: Vec<_> = lines
+            .iter()
+            .map(|l| {
+                (
+                    l.line_number,
+                    l.code.as_str(),
+                    l.marker.as_ref().unwrap().symbol,
+                )
+            })
+            .collect();
+        assert_eq!(shown, [(2, "b", '-'), (3, "d", '+')]);
+        assert_eq!((hunks[0].file.line, hunks[0].file.col), (2, 1));
+    }
+
+    #[test]
+    fn malformed_hunks_are_errors() {
+        let err = parse("--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n", "change")
+            .err()
+            .unwrap();
+        assert_eq!(err.line, 4);
+        let err = parse("--- a/f\n+++ b/f\n@@ -1 @@\n", "change")
+            .err()
+            .unwrap();
+        assert_eq!(err.line, 3);
+        let err = parse("@@ -1 +1 @@\n-a\n+b\n", "change").err().unwrap();
+        assert_eq!(err.line, 1);
+    }
+}
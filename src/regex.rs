@@ -0,0 +1,636 @@
+//! A small backtracking regex engine, for [span locators](crate::locate).
+//!
+//! Supports the common subset of Perl-style syntax:
+//! - literals, and `\` to escape any of `\.^$|?*+()[]{}`
+//! - `.`, which matches anything
+//! - classes like `[a-z_]` and `[^0-9]`
+//! - `\d`, `\w`, `\s`, and their negations `\D`, `\W`, `\S`
+//! - the anchors `^`, `$`, and `\b`
+//! - `*`, `+`, `?`, `{n}`, `{n,}`, and `{n,m}`, each made lazy by a trailing `?`
+//! - groups `(...)` and `(?:...)`, and alternation `|`
+//!
+//! Matching is leftmost-first, like Perl: of the matches starting at the
+//! leftmost position, the one the pattern reaches first wins.
+//!
+//! Backtracking has limits. A search gives up after a million steps, so
+//! patterns like `(a|a)*b` that backtrack exponentially find nothing on long
+//! lines instead of hanging. And matching recurses for each node, including
+//! each iteration of a repeated group, so a match can only go a thousand
+//! levels deep: `(ab)*` matches at most a few hundred `ab`s. Repeats of a
+//! single character, like `\w+` or `.*`, don't recurse, so they have no limit.
+//! [`Regex::try_find_iter`] tells a search that gave up apart from one that
+//! found nothing.
+//!
+//! ```rust
+//! use frack::regex::Regex;
+//!
+//! let regex = Regex::new(r"\bfoo\w*").unwrap();
+//! let line = "let food = foo_bar();";
+//! assert_eq!(regex.find(line), Some(4..8));
+//!
+//! let matches: Vec<_> = regex.find_iter(line).map(|r| &line[r]).collect();
+//! assert_eq!(matches, ["food", "foo_bar"]);
+//! ```
+
+use std::{cell::Cell, fmt, ops::Range};
+
+/// How many nodes a search may try matching before it gives up.
+const MAX_STEPS: usize = 1_000_000;
+
+/// How deeply matching may recurse before it gives up on a path. Each node
+/// matched, including each iteration of a repeated group, recurses once.
+const MAX_DEPTH: usize = 1_000;
+
+/// A compiled regular expression.
+#[derive(Clone, Debug)]
+pub struct Regex {
+    pattern: String,
+    root: Alternation,
+}
+
+/// Alternatives, each a sequence of nodes.
+type Alternation = Vec<Vec<Node>>;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary,
+    Group(Alternation),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+/// Why a pattern isn't a valid [`Regex`].
+#[derive(Debug)]
+pub struct RegexError {
+    /// The character offset in the pattern where the problem is.
+    pub offset: usize,
+
+    pub kind: RegexErrorKind,
+}
+
+#[derive(Debug)]
+pub enum RegexErrorKind {
+    /// A `(` without a `)`.
+    UnclosedGroup,
+
+    /// A `)` without a `(`.
+    UnopenedGroup,
+
+    /// A `[` without a `]`.
+    UnclosedClass,
+
+    /// A `\` at the end of the pattern.
+    TrailingEscape,
+
+    /// A quantifier with nothing to repeat, e.g. the `*` in `a|*`.
+    NothingToRepeat,
+
+    /// A `{n,m}` with `n > m`, or that isn't closed.
+    InvalidRepetition,
+
+    /// A class range like `z-a`.
+    InvalidRange,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            RegexErrorKind::UnclosedGroup => "unclosed group",
+            RegexErrorKind::UnopenedGroup => "unopened group",
+            RegexErrorKind::UnclosedClass => "unclosed character class",
+            RegexErrorKind::TrailingEscape => "trailing `\\`",
+            RegexErrorKind::NothingToRepeat => "nothing to repeat",
+            RegexErrorKind::InvalidRepetition => "invalid repetition",
+            RegexErrorKind::InvalidRange => "invalid character range",
+        };
+
+        write!(f, "{kind} at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+/// A search that gave up, because it took too many steps or recursed too
+/// deeply.
+#[derive(Debug)]
+pub struct TooComplex;
+
+impl fmt::Display for TooComplex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the search backtracked too much, so it gave up")
+    }
+}
+
+impl std::error::Error for TooComplex {}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+
+        let root = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error(RegexErrorKind::UnopenedGroup));
+        }
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            root,
+        })
+    }
+
+    /// The pattern the regex was compiled from.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, haystack: &str) -> bool {
+        self.find(haystack).is_some()
+    }
+
+    /// The byte range of the leftmost match in `haystack`, if any.
+    pub fn find(&self, haystack: &str) -> Option<Range<usize>> {
+        self.find_iter(haystack).next()
+    }
+
+    /// The byte ranges of the successive non-overlapping matches in
+    /// `haystack`, up to the first search that gives up.
+    pub fn find_iter<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.try_find_iter(haystack).map_while(Result::ok)
+    }
+
+    /// Like [`find_iter`](Self::find_iter), but ends with [`TooComplex`] if a
+    /// search gives up, instead of just ending.
+    pub fn try_find_iter<'a>(
+        &'a self,
+        haystack: &'a str,
+    ) -> impl Iterator<Item = Result<Range<usize>, TooComplex>> + 'a {
+        let chars: Vec<(usize, char)> = haystack.char_indices().collect();
+        let offset = move |i: usize| chars.get(i).map_or(haystack.len(), |&(b, _)| b);
+
+        let text: Vec<char> = haystack.chars().collect();
+        let mut start = Some(0);
+        std::iter::from_fn(move || {
+            let (a, b) = match self.find_chars(&text, start?) {
+                Ok(found) => found?,
+                Err(err) => {
+                    start = None;
+                    return Some(Err(err));
+                }
+            };
+
+            // step past empty matches so they aren't found again
+            start = Some(if b == a { b + 1 } else { b });
+            Some(Ok(offset(a)..offset(b)))
+        })
+    }
+
+    /// The char range of the leftmost match in `text` at or after `from`.
+    ///
+    /// If the search gave up anywhere before that match, the match might not be
+    /// the leftmost-first one, so it's [`TooComplex`] too.
+    fn find_chars(&self, text: &[char], from: usize) -> Result<Option<(usize, usize)>, TooComplex> {
+        let matcher = Matcher {
+            text,
+            steps: Cell::new(MAX_STEPS),
+            depth: Cell::new(0),
+            gave_up: Cell::new(false),
+        };
+
+        let found = (from..=text.len()).find_map(|start| {
+            let mut end = None;
+            matcher
+                .alternation(&self.root, start, &mut |j| {
+                    end = Some(j);
+                    true
+                })
+                .then(|| (start, end.unwrap()))
+        });
+
+        match matcher.gave_up.get() {
+            true => Err(TooComplex),
+            false => Ok(found),
+        }
+    }
+}
+
+impl fmt::Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, kind: RegexErrorKind) -> RegexError {
+        RegexError {
+            offset: self.pos,
+            kind,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let eaten = self.peek() == Some(c);
+        self.pos += eaten as usize;
+        eaten
+    }
+
+    fn alternation(&mut self) -> Result<Alternation, RegexError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            let node = match c {
+                '|' | ')' => break,
+                '*' | '+' | '?' | '{' => {
+                    let Some(node) = nodes.pop() else {
+                        return Err(self.error(RegexErrorKind::NothingToRepeat));
+                    };
+
+                    self.repeat(node)?
+                }
+                _ => self.atom()?,
+            };
+
+            nodes.push(node);
+        }
+
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let c = self.chars[self.pos];
+        self.pos += 1;
+
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // `(?:...)` is the same as `(...)`, since nothing is captured
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+
+                let group = self.alternation()?;
+                if !self.eat(')') {
+                    self.pos = start;
+                    return Err(self.error(RegexErrorKind::UnclosedGroup));
+                }
+
+                Node::Group(group)
+            }
+            '[' => self.class(start)?,
+            '\\' => match self.escape()? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Class(ranges, negated) => Node::Class { ranges, negated },
+                Escape::WordBoundary => Node::WordBoundary,
+            },
+            c => Node::Char(c),
+        })
+    }
+
+    /// Parse the quantifier after `node`.
+    fn repeat(&mut self, node: Node) -> Result<Node, RegexError> {
+        if matches!(node, Node::Repeat { .. }) {
+            return Err(self.error(RegexErrorKind::NothingToRepeat));
+        }
+
+        let (min, max) = match self.chars[self.pos] {
+            '*' => (0, None),
+            '+' => (1, None),
+            '?' => (0, Some(1)),
+            _ => {
+                let start = self.pos;
+                let close = self.chars[start..]
+                    .iter()
+                    .position(|&c| c == '}')
+                    .ok_or_else(|| self.error(RegexErrorKind::InvalidRepetition))?;
+
+                let inner: String = self.chars[start + 1..start + close].iter().collect();
+                let number = |s: &str| s.trim().parse::<usize>().ok();
+                let bounds = match inner.split_once(',') {
+                    None => number(&inner).map(|n| (n, Some(n))),
+                    Some((n, "")) => number(n).map(|n| (n, None)),
+                    Some((n, m)) => number(n).zip(number(m)).map(|(n, m)| (n, Some(m))),
+                };
+
+                match bounds {
+                    Some((n, m)) if m.is_none_or(|m| n <= m) => {
+                        self.pos = start + close;
+                        (n, m)
+                    }
+                    _ => return Err(self.error(RegexErrorKind::InvalidRepetition)),
+                }
+            }
+        };
+
+        self.pos += 1;
+        let greedy = !self.eat('?');
+
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parse a `[...]` class, after the `[` at `start`.
+    fn class(&mut self, start: usize) -> Result<Node, RegexError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+
+        let mut first = true;
+        loop {
+            let Some(c) = self.peek() else {
+                self.pos = start;
+                return Err(self.error(RegexErrorKind::UnclosedClass));
+            };
+
+            self.pos += 1;
+
+            // a `]` right after the `[` is literal
+            if c == ']' && !first {
+                break;
+            }
+
+            first = false;
+            let low = match c {
+                '\\' => match self.escape()? {
+                    Escape::Char(c) => c,
+                    Escape::Class(r, false) => {
+                        ranges.extend(r);
+                        continue;
+                    }
+                    _ => {
+                        self.pos -= 1;
+                        return Err(self.error(RegexErrorKind::InvalidRange));
+                    }
+                },
+                c => c,
+            };
+
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']');
+            if !is_range {
+                ranges.push((low, low));
+                continue;
+            }
+
+            self.pos += 1;
+            let high = match self.chars[self.pos] {
+                '\\' => {
+                    self.pos += 1;
+                    match self.escape()? {
+                        Escape::Char(c) => c,
+                        _ => return Err(self.error(RegexErrorKind::InvalidRange)),
+                    }
+                }
+                c => {
+                    self.pos += 1;
+                    c
+                }
+            };
+
+            if low > high {
+                return Err(self.error(RegexErrorKind::InvalidRange));
+            }
+
+            ranges.push((low, high));
+        }
+
+        Ok(Node::Class { ranges, negated })
+    }
+
+    /// Parse what follows a `\`.
+    fn escape(&mut self) -> Result<Escape, RegexError> {
+        let Some(c) = self.peek() else {
+            return Err(self.error(RegexErrorKind::TrailingEscape));
+        };
+
+        self.pos += 1;
+        Ok(match c {
+            'd' | 'D' => Escape::Class(DIGIT.to_vec(), c == 'D'),
+            'w' | 'W' => Escape::Class(WORD.to_vec(), c == 'W'),
+            's' | 'S' => Escape::Class(SPACE.to_vec(), c == 'S'),
+            'b' => Escape::WordBoundary,
+            'n' => Escape::Char('\n'),
+            't' => Escape::Char('\t'),
+            c => Escape::Char(c),
+        })
+    }
+}
+
+enum Escape {
+    Char(char),
+    Class(Vec<(char, char)>, bool),
+    WordBoundary,
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+struct Matcher<'a> {
+    text: &'a [char],
+
+    /// How many more nodes may be tried.
+    steps: Cell<usize>,
+
+    /// How deeply `node` is recursing.
+    depth: Cell<usize>,
+
+    /// Whether a path was abandoned for hitting one of the limits.
+    gave_up: Cell<bool>,
+}
+
+impl Matcher<'_> {
+    /// Match `alternatives` at `i`, calling `then` with the end of each
+    /// match until it returns `true`.
+    fn alternation(
+        &self,
+        alternatives: &Alternation,
+        i: usize,
+        then: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        alternatives.iter().any(|seq| self.sequence(seq, i, then))
+    }
+
+    fn sequence(&self, nodes: &[Node], i: usize, then: &mut dyn FnMut(usize) -> bool) -> bool {
+        match nodes.split_first() {
+            None => then(i),
+            Some((node, rest)) => self.node(node, i, &mut |j| self.sequence(rest, j, then)),
+        }
+    }
+
+    /// Match `node` at `i`, or give up if the search has gone on too long.
+    fn node(&self, node: &Node, i: usize, then: &mut dyn FnMut(usize) -> bool) -> bool {
+        let (steps, depth) = (self.steps.get(), self.depth.get());
+        if steps == 0 || depth == MAX_DEPTH {
+            self.gave_up.set(true);
+            return false;
+        }
+
+        self.steps.set(steps - 1);
+        self.depth.set(depth + 1);
+        let matched = self.step(node, i, then);
+        self.depth.set(depth);
+        matched
+    }
+
+    fn step(&self, node: &Node, i: usize, then: &mut dyn FnMut(usize) -> bool) -> bool {
+        let is_word = |i: Option<usize>| {
+            i.and_then(|i| self.text.get(i))
+                .is_some_and(|&c| c.is_alphanumeric() || c == '_')
+        };
+
+        match node {
+            Node::Char(_) | Node::Any | Node::Class { .. } => {
+                self.single(node, i) == Some(true) && then(i + 1)
+            }
+            Node::Start => i == 0 && then(i),
+            Node::End => i == self.text.len() && then(i),
+            Node::WordBoundary => is_word(i.checked_sub(1)) != is_word(Some(i)) && then(i),
+            Node::Group(alternatives) => self.alternation(alternatives, i, then),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, *min, *max, *greedy, 0, i, then),
+        }
+    }
+
+    /// Whether `node` matches the character at `i`, or `None` if `node`
+    /// doesn't match a single character.
+    fn single(&self, node: &Node, i: usize) -> Option<bool> {
+        let c = self.text.get(i).copied();
+        Some(match node {
+            Node::Char(x) => c == Some(*x),
+            Node::Any => c.is_some(),
+            Node::Class { ranges, negated } => {
+                c.is_some_and(|c| ranges.iter().any(|&(a, b)| a <= c && c <= b) != *negated)
+            }
+            _ => return None,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        count: usize,
+        i: usize,
+        then: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        // repeats of a single character, like `\w+`, loop instead of recursing
+        if self.single(node, i).is_some() {
+            let max = max.map_or(self.text.len() - i, |max| max.min(self.text.len() - i));
+            let n = (0..max)
+                .take_while(|&k| self.single(node, i + k) == Some(true))
+                .count();
+
+            let mut counts = min..n + 1;
+            return if greedy {
+                counts.rev().any(|k| then(i + k))
+            } else {
+                counts.any(|k| then(i + k))
+            };
+        }
+
+        let more = |then: &mut dyn FnMut(usize) -> bool| {
+            max.is_none_or(|max| count < max)
+                && self.node(node, i, &mut |j| {
+                    // an empty iteration can't lead anywhere new
+                    (j > i || count < min)
+                        && self.repeat(node, min, max, greedy, count + 1, j, then)
+                })
+        };
+
+        // greedy repeats try another iteration first, lazy ones try stopping
+        let done = count >= min;
+        if !greedy && done && then(i) {
+            return true;
+        }
+
+        more(then) || greedy && done && then(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Regex, TooComplex};
+
+    fn find(pattern: &str, haystack: &str) -> Option<std::ops::Range<usize>> {
+        Regex::new(pattern).unwrap().find(haystack)
+    }
+
+    #[test]
+    fn repeats() {
+        assert_eq!(find("a*", "aaab"), Some(0..3));
+        assert_eq!(find("a*?b", "aaab"), Some(0..4));
+        assert_eq!(find("a{2,3}", "aaaa"), Some(0..3));
+        assert_eq!(find("a{2,}?", "aaaa"), Some(0..2));
+        assert_eq!(find("(a|ab)c", "abc"), Some(0..3));
+        assert_eq!(find("(?:)*x", "x"), Some(0..1));
+    }
+
+    #[test]
+    fn exponential_backtracking_gives_up() {
+        let haystack = "a".repeat(40) + "c";
+        assert_eq!(find("(a|a)*b", &haystack), None);
+        assert_eq!(find("(a|a)*c", &haystack), Some(0..41));
+
+        let regex = Regex::new("(a|a)*b").unwrap();
+        let found: Vec<_> = regex.try_find_iter(&haystack).collect();
+        assert!(matches!(found[..], [Err(TooComplex)]));
+    }
+
+    #[test]
+    fn long_matches_dont_overflow_the_stack() {
+        let haystack = "a".repeat(100_000);
+        assert_eq!(find(".*x", &haystack), None);
+        assert_eq!(find("(?:(?:(?:a)))*x", &haystack), None);
+        assert_eq!(find(r"\w+", &haystack), Some(0..100_000));
+
+        let regex = Regex::new("(?:(?:(?:a)))*x").unwrap();
+        let found: Vec<_> = regex.try_find_iter(&haystack).collect();
+        assert!(matches!(found[..], [Err(TooComplex)]));
+        let found: Vec<_> = regex.try_find_iter("aax").collect();
+        assert!(matches!(found[..], [Ok(_)]));
+    }
+}
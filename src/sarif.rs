@@ -0,0 +1,355 @@
+//! SARIF 2.1.0 output, for code-scanning dashboards.
+//!
+//! ```rust,no_run
+//! let error = frack::error! {
+//!     "E0308", "mismatched types";
+//!     "src/main.rs", 2, 18;
+//!     "    let x: i32 = \"hello\";";
+//!     17..=23 => "expected `i32`, found `&str`";
+//! };
+//!
+//! let log = frack::sarif::Sarif::new("mytool").log(&[error.into()]);
+//! println!("{log}");
+//! ```
+
+use std::ops::Range;
+
+use crate::codes::Registry;
+use crate::json::Value;
+use crate::{edit, Diagnostic, Level, Line};
+
+/// Serializes diagnostics to a SARIF log.
+pub struct Sarif<'a> {
+    tool: &'a str,
+    version: Option<&'a str>,
+    registry: Option<&'a Registry>,
+}
+
+impl<'a> Sarif<'a> {
+    /// Create a serializer for diagnostics produced by `tool`.
+    pub fn new(tool: &'a str) -> Self {
+        Self {
+            tool,
+            version: None,
+            registry: None,
+        }
+    }
+
+    /// Set the version of the tool.
+    pub fn version(mut self, version: &'a str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Describe the rules (error codes) using a registry's explanations.
+    pub fn registry(mut self, registry: &'a Registry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Serialize diagnostics as a complete SARIF log, with a single run.
+    pub fn log(&self, diagnostics: &[Diagnostic]) -> Value {
+        let mut rules: Vec<&str> = Vec::new();
+        for code in diagnostics.iter().filter_map(Diagnostic::error_code) {
+            if !rules.contains(&code) {
+                rules.push(code);
+            }
+        }
+
+        let results = diagnostics
+            .iter()
+            .map(|d| {
+                let mut r = result(d);
+                if let (Value::Object(fields), Some(code)) = (&mut r, d.error_code()) {
+                    let index = rules.iter().position(|&c| c == code).unwrap_or(0);
+                    fields.insert(1, ("ruleIndex".to_string(), index.into()));
+                }
+                r
+            })
+            .collect::<Vec<_>>();
+
+        let rules = rules
+            .into_iter()
+            .map(|code| {
+                let explanation = self.registry.and_then(|r| r.explain(code));
+                let mut rule = vec![("id".to_string(), code.into())];
+                if let Some(text) = explanation {
+                    rule.push((
+                        "fullDescription".to_string(),
+                        Value::object([("text", text.into()), ("markdown", text.into())]),
+                    ));
+                }
+                Value::Object(rule)
+            })
+            .collect::<Vec<_>>();
+
+        let mut driver = vec![("name".to_string(), self.tool.into())];
+        if let Some(version) = self.version {
+            driver.push(("version".to_string(), version.into()));
+        }
+        driver.push(("rules".to_string(), rules.into()));
+
+        Value::object([
+            (
+                "$schema",
+                "https://json.schemastore.org/sarif-2.1.0.json".into(),
+            ),
+            ("version", "2.1.0".into()),
+            (
+                "runs",
+                Value::Array(vec![Value::object([
+                    ("tool", Value::object([("driver", Value::Object(driver))])),
+                    ("columnKind", "unicodeCodePoints".into()),
+                    ("results", results.into()),
+                ])]),
+            ),
+        ])
+    }
+}
+
+/// Serialize a single diagnostic as a SARIF `result` object.
+pub fn result(diag: &Diagnostic) -> Value {
+    let file = diag.file();
+    let primary = diag.primary();
+
+    let mut fields = Vec::new();
+    if let Some(code) = diag.error_code() {
+        fields.push(("ruleId".to_string(), code.into()));
+    }
+
+    let level = match diag.level() {
+        Level::Error => "error",
+        Level::Warning => "warning",
+    };
+    fields.push(("level".to_string(), level.into()));
+    fields.push((
+        "message".to_string(),
+        Value::object([("text", diag.message().into())]),
+    ));
+
+    let primary_region = match primary {
+        Some(line) => {
+            let range = line
+                .marker
+                .as_ref()
+                .map_or(0..0, |m| edit::marked(line, &m.range));
+            region(line, range, true)
+        }
+
+        None => Value::object([
+            ("startLine", file.line.into()),
+            ("startColumn", file.col.into()),
+        ]),
+    };
+
+    if !file.path.is_empty() {
+        fields.push((
+            "locations".to_string(),
+            Value::Array(vec![location(&file.path, primary_region, None)]),
+        ));
+    }
+
+    let related = diag
+        .code()
+        .iter()
+        .filter(|l| !primary.is_some_and(|p| std::ptr::eq(p, *l)))
+        .filter_map(|l| {
+            let m = l.marker.as_ref()?;
+            Some((l, m))
+        })
+        .enumerate()
+        .map(|(id, (line, m))| {
+            let mut loc = location(
+                &file.path,
+                region(line, edit::marked(line, &m.range), true),
+                m.message.as_deref(),
+            );
+            if let Value::Object(fields) = &mut loc {
+                fields.insert(0, ("id".to_string(), id.into()));
+            }
+            loc
+        })
+        .collect::<Vec<_>>();
+
+    if !related.is_empty() {
+        fields.push(("relatedLocations".to_string(), related.into()));
+    }
+
+    let fixes = diag
+        .helps()
+        .filter_map(|help| {
+            let suggestion = help.suggestion.as_ref()?;
+            let replacements = edit::suggested(diag.code(), suggestion)
+                .into_iter()
+                .map(|(line, e)| {
+                    Value::object([
+                        ("deletedRegion", region(line, e.range, false)),
+                        (
+                            "insertedContent",
+                            Value::object([("text", e.replacement.into())]),
+                        ),
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            if replacements.is_empty() {
+                return None;
+            }
+
+            Some(Value::object([
+                (
+                    "description",
+                    Value::object([("text", help.message.as_str().into())]),
+                ),
+                (
+                    "artifactChanges",
+                    Value::Array(vec![Value::object([
+                        (
+                            "artifactLocation",
+                            Value::object([("uri", uri(&file.path))]),
+                        ),
+                        ("replacements", replacements.into()),
+                    ])]),
+                ),
+            ]))
+        })
+        .collect::<Vec<_>>();
+
+    if !fixes.is_empty() {
+        fields.push(("fixes".to_string(), fixes.into()));
+    }
+
+    Value::Object(fields)
+}
+
+/// A region of a single line, with optional snippet.
+fn region(line: &Line, range: Range<usize>, snippet: bool) -> Value {
+    let column = |byte| edit::column(&line.code, byte) + 1;
+
+    let mut fields = vec![
+        ("startLine".to_string(), line.line_number.into()),
+        ("startColumn".to_string(), column(range.start).into()),
+        ("endLine".to_string(), line.line_number.into()),
+        ("endColumn".to_string(), column(range.end).into()),
+    ];
+
+    if snippet {
+        fields.push((
+            "snippet".to_string(),
+            Value::object([("text", line.code.as_str().into())]),
+        ));
+    }
+
+    Value::Object(fields)
+}
+
+fn location(path: &str, region: Value, message: Option<&str>) -> Value {
+    let mut fields = vec![(
+        "physicalLocation".to_string(),
+        Value::object([
+            ("artifactLocation", Value::object([("uri", uri(path))])),
+            ("region", region),
+        ]),
+    )];
+
+    if let Some(message) = message {
+        fields.push((
+            "message".to_string(),
+            Value::object([("text", message.into())]),
+        ));
+    }
+
+    Value::Object(fields)
+}
+
+/// A path as a relative URI reference.
+fn uri(path: &str) -> Value {
+    let mut uri = String::new();
+    for b in path.replace('\\', "/").bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(b as char)
+            }
+            _ => uri.push_str(&format!("%{b:02X}")),
+        }
+    }
+    uri.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::result;
+    use crate::json::Value;
+    use crate::{edit, fix, frackfile, Diagnostic};
+
+    const SOURCE: &str = "let x = 1;\nlet y = 2;\nlet z = x + y;\n";
+
+    /// An error about line 1 of [`SOURCE`], with a help suggesting `line`.
+    fn diagnostic(line: &str) -> Diagnostic {
+        let frack = format!(
+            "\
+error: oops
+ --> src/main.rs:1:5
+  |
+1 | let x = 1;
+  |     ^
+  |
+help: change it
+  |
+{line}
+"
+        );
+        frackfile::parse(&frack).unwrap().remove(0)
+    }
+
+    /// `SOURCE` with the replacements of the SARIF result's fixes made.
+    fn sarif_fixed(diag: &Diagnostic) -> String {
+        let result = result(diag);
+        let mut lines: Vec<String> = SOURCE.lines().map(str::to_string).collect();
+        let fixes = result.get("fixes").and_then(Value::as_array).unwrap_or(&[]);
+        for fix in fixes {
+            let changes = fix.get("artifactChanges").and_then(Value::as_array);
+            for change in changes.unwrap() {
+                let replacements = change.get("replacements").and_then(Value::as_array);
+                for replacement in replacements.unwrap() {
+                    let region = replacement.get("deletedRegion").unwrap();
+                    let n = |key| region.get(key).and_then(Value::as_usize).unwrap();
+                    let text = replacement.get("insertedContent").unwrap().get("text");
+                    let line = &mut lines[n("startLine") - 1];
+                    let range = edit::byte(line, n("startColumn") - 1)
+                        ..edit::byte(line, n("endColumn") - 1);
+                    line.replace_range(range, text.and_then(Value::as_str).unwrap());
+                }
+            }
+        }
+
+        lines.join("\n") + "\n"
+    }
+
+    /// `SOURCE` as `frack fix` fixes it.
+    fn fixed(diag: Diagnostic) -> String {
+        let fixes = fix::fixes(&[diag]);
+        fix::apply(SOURCE, &fixes).source
+    }
+
+    #[test]
+    fn fixes_match_frack_fix() {
+        for line in [
+            "1 | let _x = 1;\n  |     ~~",
+            "1 | let x = 1;\n  |     -----",
+            "3 | let z: f64 = x + y;\n  |      +++++",
+        ] {
+            let diag = diagnostic(line);
+            assert!(result(&diag).get("fixes").is_some(), "{line}");
+            assert_eq!(sarif_fixed(&diag), fixed(diagnostic(line)), "{line}");
+        }
+    }
+
+    #[test]
+    fn unresolvable_fixes_are_left_out() {
+        // what `zed` replaces isn't shown, so its region is unknown
+        let diag = diagnostic("3 | let zed = x + y;\n  |     ~~~");
+        assert!(result(&diag).get("fixes").is_none());
+        assert_eq!(fixed(diag), "let x = 1;\nlet y = 2;\nlet zed = x + y;\n");
+    }
+}
@@ -0,0 +1,65 @@
+//! `rustc`'s one-line `--error-format=short` style, as used by editors'
+//! quickfix lists and problem matchers.
+
+use std::fmt;
+
+use crate::{bold, color, normal, Diagnostic, Level};
+
+/// A diagnostic on a single line, e.g.
+/// `src/main.rs:7:9: error[E0308]: mismatched types`.
+///
+/// To display using ANSI escape codes, use the [`Display`](fmt::Display) impl.
+pub struct Short<'a> {
+    pub diagnostic: &'a Diagnostic,
+
+    /// Whether to append the primary marker's message, as `rustc` does.
+    pub label: bool,
+}
+
+impl<'a> Short<'a> {
+    /// Create a short diagnostic, with the primary label.
+    pub fn new(diagnostic: &'a Diagnostic) -> Self {
+        Self {
+            diagnostic,
+            label: true,
+        }
+    }
+
+    /// Set whether to append the primary marker's message.
+    pub fn label(mut self, label: bool) -> Self {
+        self.label = label;
+        self
+    }
+}
+
+impl fmt::Display for Short<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diag = self.diagnostic;
+        let file = diag.file();
+
+        bold(f)?;
+        if !file.path.is_empty() {
+            write!(f, "{}:{}:{}: ", file.path, file.line, file.col)?;
+        }
+
+        match diag.level() {
+            Level::Error => color(9, f)?,
+            Level::Warning => color(3, f)?,
+        }
+        write!(f, "{}", diag.level())?;
+        if let Some(code) = diag.error_code() {
+            write!(f, "[{code}]")?;
+        }
+        normal(f)?;
+
+        bold(f)?;
+        write!(f, ": {}", diag.message())?;
+
+        if let Some(label) = diag.label().filter(|_| self.label) {
+            write!(f, ": {label}")?;
+        }
+
+        normal(f)?;
+        writeln!(f)
+    }
+}
@@ -0,0 +1,205 @@
+//! `rustc`-style "did you mean" suggestions for unknown names.
+//!
+//! ```text
+//! error[E0425]: cannot find value `conut` in this scope
+//!  --> src/main.rs:3:20
+//!   |
+//! 3 |     println!("{}", conut);
+//!   |                    ^^^^^ not found in this scope
+//!   |
+//! help: a local variable with a similar name exists: `count`
+//! ```
+//!
+//! ```rust,no_run
+//! use frack::similar;
+//!
+//! let mut error = frack::error! {
+//!     "E0425", "cannot find value `conut` in this scope";
+//!     "src/main.rs", 3, 20;
+//!     "    println!(\"{}\", conut);";
+//!     19..=23 => "not found in this scope";
+//! };
+//!
+//! let locals = ["count", "total"];
+//! if let Some(help) = similar::help("a local variable", "conut", locals, Some(&error.code)) {
+//!     error.children.push(help.into());
+//! }
+//! ```
+
+use std::ops::Range;
+
+use crate::{edit, Applicability, Code, Help, Line, Marker, SuggestionStyle};
+
+/// The candidate most similar to `name`, if any is close enough.
+///
+/// Follows `rustc`'s rules, in order of priority:
+/// 1. a candidate that only differs from `name` in case;
+/// 2. the closest candidate by [`distance`], if it's within a third of
+///    `name`'s length (at least 1);
+/// 3. a candidate with the same `_`-separated words in a different order.
+///
+/// Ties go to the earliest candidate.
+pub fn best<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let candidates: Vec<&str> = candidates.into_iter().collect();
+
+    let lowercase = name.to_lowercase();
+    if let Some(c) = candidates.iter().find(|c| c.to_lowercase() == lowercase) {
+        return Some(c);
+    }
+
+    let max = name.chars().count().max(3) / 3;
+    let closest = candidates
+        .iter()
+        .map(|c| (distance(name, c), c))
+        .filter(|&(d, _)| d <= max)
+        .min_by_key(|&(d, _)| d);
+    if let Some((_, c)) = closest {
+        return Some(c);
+    }
+
+    let words = sorted_words(name);
+    candidates.into_iter().find(|c| sorted_words(c) == words)
+}
+
+/// The edit distance between two strings: the number of characters that must
+/// be inserted, removed, replaced, or swapped with their neighbor to turn one
+/// into the other.
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // rows for the previous two characters of `a`, for transpositions
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + (a[i - 1] != b[j - 1]) as usize;
+            row[j] = substitution.min(previous[j] + 1).min(row[j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+
+        before = std::mem::replace(&mut previous, row);
+    }
+
+    previous[b.len()]
+}
+
+/// A help suggesting the candidate most similar to `name`, or `None` if none
+/// are close enough (see [`best`]).
+///
+/// `what` names the kind of thing, with its article, e.g. `"a local
+/// variable"`, giving `a local variable with a similar name exists: `count``.
+///
+/// If `code` is given, the help suggests its marked line with `name` replaced
+/// in the marked span (or the whole span replaced if it doesn't contain
+/// `name`). Otherwise, or if no line is marked, the help is just the message.
+pub fn help<'a>(
+    what: &str,
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    code: Option<&Code>,
+) -> Option<Help> {
+    let similar = best(name, candidates)?;
+    let suggestion = code.and_then(|code| replaced(code, name, similar));
+
+    // an inline suggestion already ends with the name
+    let message = match suggestion {
+        Some(_) => format!("{what} with a similar name exists"),
+        None => format!("{what} with a similar name exists: `{similar}`"),
+    };
+
+    Some(Help {
+        message,
+        suggestion,
+        style: SuggestionStyle::Auto,
+        applicability: Applicability::MaybeIncorrect,
+    })
+}
+
+/// The marked line of `code`, preferring a primary (`^`) marker, with `name`
+/// replaced by `similar` in the marked span.
+fn replaced(code: &Code, name: &str, similar: &str) -> Option<Code> {
+    let line = code
+        .iter()
+        .find(|l| l.marker.as_ref().is_some_and(|m| m.symbol == '^'))
+        .or_else(|| code.iter().find(|l| l.marker.is_some()))?;
+    let range = &line.marker.as_ref()?.range;
+
+    // marker ranges are columns, so find their byte offsets
+    let Range { start, end } = edit::marked(line, range);
+    let span = &line.code[start..end];
+
+    let (start, end) = match span.find(name).filter(|_| !name.is_empty()) {
+        Some(offset) => (start + offset, start + offset + name.len()),
+        None => (start, end),
+    };
+
+    let code = format!("{}{similar}{}", &line.code[..start], &line.code[end..]);
+    let column = edit::column(&line.code, start);
+    let marker = Marker {
+        range: column..=column + similar.chars().count().max(1) - 1,
+        symbol: '~',
+        color: 10,
+        message: None,
+        color_span: true,
+    };
+
+    Some(Code(vec![Line {
+        code,
+        line_number: line.line_number,
+        marker: Some(marker),
+    }]))
+}
+
+/// The `_`-separated words of `name`, sorted.
+fn sorted_words(name: &str) -> Vec<&str> {
+    let mut words: Vec<&str> = name.split('_').collect();
+    words.sort_unstable();
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best, distance};
+
+    #[test]
+    fn distances() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("conut", "count"), 1);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("abc", "abc"), 0);
+        assert_eq!(distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn close_enough_is_a_third_of_the_length() {
+        // names of up to 5 characters may be 1 edit away
+        assert_eq!(best("fo", ["foo"]), Some("foo"));
+        assert_eq!(best("ab", ["xy"]), None);
+        assert_eq!(best("conut", ["count"]), Some("count"));
+        assert_eq!(best("cnut", ["count"]), None);
+
+        // 6 characters may be 2 away, and 9 may be 3
+        assert_eq!(best("abcdef", ["abcxyf"]), Some("abcxyf"));
+        assert_eq!(best("abcdef", ["abcxyz"]), None);
+        assert_eq!(best("abcdefghi", ["abcdefxyz"]), Some("abcdefxyz"));
+        assert_eq!(best("abcdefghi", ["abcdewxyz"]), None);
+    }
+
+    #[test]
+    fn priorities() {
+        // case beats distance, and distance beats reordered words
+        assert_eq!(best("FOO", ["FO", "foo"]), Some("foo"));
+        assert_eq!(best("a_b", ["b_a", "a_c"]), Some("a_c"));
+        assert_eq!(best("count_total", ["total_count"]), Some("total_count"));
+
+        // the closest wins, then the earliest
+        assert_eq!(best("abcdef", ["abcxyf", "abcdex"]), Some("abcdex"));
+        assert_eq!(best("cat", ["bat", "hat"]), Some("bat"));
+        assert_eq!(best("cat", []), None);
+    }
+}
@@ -0,0 +1,952 @@
+//! Other compilers' house styles, for rendering diagnostics as they would.
+//!
+//! ```rust,no_run
+//! use frack::style::{Fancy, Gcc, Python, Styled, Tsc};
+//!
+//! let diag = frack::Diagnostic::from(frack::error! {
+//!     "E0308", "mismatched types";
+//!     "src/main.c", 7, 13;
+//!     "    int x = \"five\";";
+//!     "`\"five\"`" => "char *";
+//! });
+//!
+//! // src/main.c:7:13: error: mismatched types [E0308]
+//! //     7 |     int x = "five";
+//! //       |             ^~~~~~
+//! //       |             |
+//! //       |             char *
+//! print!("{}", Styled::new(&diag, &Gcc));
+//!
+//! print!("{}", Styled::new(&diag, &Tsc));
+//! print!("{}", Styled::new(&diag, &Python));
+//! print!("{}", Styled::new(&diag, &Fancy::detect()));
+//! ```
+
+use std::fmt;
+
+use crate::{
+    bold, color, descendants, edit, normal, ChildKind, Code, Diagnostic, Help, Level, Line, Marker,
+    Repeat, SuggestionStyle,
+};
+
+/// A way of writing out diagnostics.
+pub trait Style {
+    /// Write out `diagnostic` with ANSI escape codes.
+    fn write(&self, diagnostic: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// A diagnostic, displayed in some [`Style`].
+///
+/// To display using ANSI escape codes, use the [`Display`](fmt::Display) impl.
+pub struct Styled<'a> {
+    pub diagnostic: &'a Diagnostic,
+    pub style: &'a dyn Style,
+}
+
+impl<'a> Styled<'a> {
+    pub fn new(diagnostic: &'a Diagnostic, style: &'a dyn Style) -> Self {
+        Self { diagnostic, style }
+    }
+}
+
+impl fmt::Display for Styled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.style.write(self.diagnostic, f)
+    }
+}
+
+/// `rustc`'s style, the same as the [`Display`](fmt::Display) impl of
+/// [`Diagnostic`].
+pub struct Rustc;
+
+impl Style for Rustc {
+    fn write(&self, diagnostic: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{diagnostic}")
+    }
+}
+
+/// GCC's style, with a line number gutter, labels, and fix-it hints:
+///
+/// ```text
+/// src/main.c:3:5: error: unknown type name 'itn'
+///     3 |     itn x = 0;
+///       |     ^~~
+///       |     int
+/// ```
+///
+/// Error codes go at the end of the message, like GCC's `[-Wflag]`s. Helps
+/// become notes, and suggestions that edit the code are shown as fix-it hints
+/// under it.
+pub struct Gcc;
+
+impl Style for Gcc {
+    fn write(&self, diagnostic: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        c_family(diagnostic, false, f)
+    }
+}
+
+/// Clang's classic style, like [`Gcc`]'s but without the gutter or labels,
+/// and with green underlines:
+///
+/// ```text
+/// src/main.c:3:5: error: unknown type name 'itn'
+///     itn x = 0;
+///     ^~~
+///     int
+/// ```
+pub struct Clang;
+
+impl Style for Clang {
+    fn write(&self, diagnostic: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        c_family(diagnostic, true, f)
+    }
+}
+
+/// GCC's or Clang's style.
+fn c_family(diag: &Diagnostic, clang: bool, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let level_color = match diag.level() {
+        Level::Error => 9,
+        Level::Warning => 13,
+    };
+    let note_color = if clang { 8 } else { 14 };
+
+    let header = |f: &mut fmt::Formatter<'_>, line: usize, col: usize| {
+        bold(f)?;
+        let file = diag.file();
+        if !file.path.is_empty() {
+            write!(f, "{}:{line}:{col}: ", file.path)?;
+        }
+        normal(f)
+    };
+
+    header(f, diag.file().line, diag.file().col)?;
+    bold(f)?;
+    color(level_color, f)?;
+    write!(f, "{}: ", diag.level())?;
+    normal(f)?;
+    bold(f)?;
+    write!(f, "{}", diag.message())?;
+    if let Some(code) = diag.error_code() {
+        write!(f, " [{code}]")?;
+    }
+    normal(f)?;
+    writeln!(f)?;
+
+    // helps whose suggestions only edit lines of the code are fix-it hints
+    let fixits: Vec<&Help> = diag.helps().filter(|h| is_fixit(diag.code(), h)).collect();
+    let primary = diag.primary().and_then(|l| l.marker.as_ref());
+
+    let style = CStyle {
+        gutter: !clang,
+        labels: !clang,
+        caret_color: if clang { 10 } else { level_color },
+        primary,
+    };
+    style.code(diag.code(), &fixits, f)?;
+
+    for (_, child) in descendants(diag.children()) {
+        let help = match &child.kind {
+            ChildKind::Help(help) => help,
+            ChildKind::Note(note) | ChildKind::Warning(note) => {
+                let (level, level_color) = match child.kind {
+                    ChildKind::Warning(_) => ("warning", 13),
+                    _ => ("note", note_color),
+                };
+
+                bold(f)?;
+                if !diag.file().path.is_empty() {
+                    write!(f, "{}: ", diag.file().path)?;
+                }
+                color(level_color, f)?;
+                write!(f, "{level}: ")?;
+                normal(f)?;
+                writeln!(f, "{}", note.0)?;
+                continue;
+            }
+        };
+
+        header(f, diag.file().line, diag.file().col)?;
+        bold(f)?;
+        color(note_color, f)?;
+        write!(f, "note: ")?;
+        normal(f)?;
+        match help
+            .inline()
+            .filter(|_| !fixits.iter().any(|h| std::ptr::eq(*h, help)))
+        {
+            Some(snippet) => writeln!(f, "{}: '{snippet}'", help.message)?,
+            None => writeln!(f, "{}", help.message)?,
+        }
+
+        match &help.suggestion {
+            Some(s)
+                if help.inline().is_none() && !fixits.iter().any(|h| std::ptr::eq(*h, help)) =>
+            {
+                let style = CStyle {
+                    caret_color: 10,
+                    primary: None,
+                    ..style
+                };
+                style.code(s, &[], f)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `help`'s suggestion only edits lines shown in `code`.
+fn is_fixit(code: &Code, help: &Help) -> bool {
+    let Some(suggestion) = help.suggestion.as_ref().filter(|s| !s.is_empty()) else {
+        return false;
+    };
+
+    let shown = |number| code.iter().any(|l| l.line_number == number);
+    suggestion.iter().all(|l| shown(l.line_number))
+        && edit::suggested(code, suggestion)
+            .iter()
+            .all(|(line, _)| code.iter().any(|l| std::ptr::eq(l, *line)))
+}
+
+#[derive(Clone, Copy)]
+struct CStyle<'a> {
+    gutter: bool,
+    labels: bool,
+    caret_color: u8,
+
+    /// The marker to start with a `^`; the others are all `~`s.
+    primary: Option<&'a Marker>,
+}
+
+impl CStyle<'_> {
+    /// Write out `code`, with the edits of `fixits` under the lines they
+    /// edit.
+    fn code(&self, code: &Code, fixits: &[&Help], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = |f: &mut fmt::Formatter<'_>, number: Option<usize>| {
+            if !self.gutter {
+                return Ok(());
+            }
+
+            match number {
+                Some(n) => write!(f, "{n: >5} | "),
+                None => write!(f, "{: >5} | ", ""),
+            }
+        };
+
+        for (line, markers) in grouped(code) {
+            gutter(f, Some(line.line_number))?;
+            writeln!(f, "{}", line.code)?;
+
+            if !markers.is_empty() {
+                gutter(f, None)?;
+                underline(f, &markers, |m| {
+                    let len = m.range.end().saturating_sub(*m.range.start()) + 1;
+                    match self.primary {
+                        Some(p) if std::ptr::eq(p, m) => {
+                            (format!("^{}", Repeat(len - 1, '~')), self.caret_color)
+                        }
+                        Some(_) => (Repeat(len, '~').to_string(), 12),
+                        None => (Repeat(len, m.symbol).to_string(), self.caret_color),
+                    }
+                })?;
+                writeln!(f)?;
+            }
+
+            // fix-it hints, as many to a row as fit
+            let mut hints: Vec<(usize, String)> = Vec::new();
+            for help in fixits {
+                let suggestion = help.suggestion.as_ref().unwrap();
+                for new in suggestion
+                    .iter()
+                    .filter(|l| l.line_number == line.line_number)
+                {
+                    let new = edit::revised(new);
+                    let chars: Vec<char> = new.chars().collect();
+                    for (old, new) in edit::changes(&line.code, &new) {
+                        let text = match new.start == new.end {
+                            true => Repeat(old.end - old.start, '-').to_string(),
+                            false => chars[new].iter().collect(),
+                        };
+                        if !hints.contains(&(old.start, text.clone())) {
+                            hints.push((old.start, text));
+                        }
+                    }
+                }
+            }
+            hints.sort();
+
+            while !hints.is_empty() {
+                let mut column = 0;
+                let (row, rest): (Vec<_>, Vec<_>) = hints.into_iter().partition(|(start, text)| {
+                    let fits = *start >= column;
+                    if fits {
+                        column = start + text.chars().count().max(1);
+                    }
+                    fits
+                });
+
+                gutter(f, None)?;
+                let mut column = 0;
+                for (start, text) in row {
+                    write!(f, "{: >pad$}", "", pad = start - column)?;
+                    color(10, f)?;
+                    write!(f, "{text}")?;
+                    normal(f)?;
+                    column = start + text.chars().count();
+                }
+                writeln!(f)?;
+
+                hints = rest;
+            }
+
+            if self.labels {
+                let mut labeled: Vec<&Marker> = markers
+                    .into_iter()
+                    .filter(|m| m.message.is_some())
+                    .collect();
+                labeled.sort_by_key(|m| *m.range.start());
+
+                let color = |m: &Marker| match self.primary {
+                    Some(p) if std::ptr::eq(p, m) => self.caret_color,
+                    Some(_) => 12,
+                    None => self.caret_color,
+                };
+
+                // connectors, then the labels from right to left, each with
+                // the connectors of the labels left of it
+                let connectors = |f: &mut fmt::Formatter<'_>, labeled: &[&Marker]| {
+                    gutter(f, None)?;
+                    let mut column = 0;
+                    for m in labeled {
+                        let pad = m.range.start().saturating_sub(column);
+                        write!(f, "{: >pad$}", "")?;
+                        self::color(color(m), f)?;
+                        write!(f, "|")?;
+                        normal(f)?;
+                        column = m.range.start() + 1;
+                    }
+                    Ok::<_, fmt::Error>(column)
+                };
+
+                if !labeled.is_empty() {
+                    connectors(f, &labeled)?;
+                    writeln!(f)?;
+                }
+
+                for i in (0..labeled.len()).rev() {
+                    let column = connectors(f, &labeled[..i])?;
+                    let m = labeled[i];
+                    let pad = m.range.start().saturating_sub(column);
+                    write!(f, "{: >pad$}", "")?;
+                    self::color(color(m), f)?;
+                    write!(f, "{}", m.message.as_deref().unwrap_or_default())?;
+                    normal(f)?;
+                    writeln!(f)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// TypeScript's `tsc --pretty` style:
+///
+/// ```text
+/// src/index.ts:3:7 - error TS2322: Type 'string' is not assignable to type 'number'.
+///
+/// 3 const x: number = "five";
+///         ~
+/// ```
+///
+/// Numeric error codes get a `TS` prefix. Marker labels, notes, and helps
+/// without suggestions are chained under the message; helps with suggestions
+/// become related information.
+pub struct Tsc;
+
+impl Style for Tsc {
+    fn write(&self, diag: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = diag.file();
+        let location = |f: &mut fmt::Formatter<'_>, line: usize, col: usize| {
+            color(14, f)?;
+            write!(f, "{}", file.path)?;
+            normal(f)?;
+            write!(f, ":")?;
+            color(11, f)?;
+            write!(f, "{line}")?;
+            normal(f)?;
+            write!(f, ":")?;
+            color(11, f)?;
+            write!(f, "{col}")?;
+            normal(f)
+        };
+
+        let level_color = match diag.level() {
+            Level::Error => 9,
+            Level::Warning => 11,
+        };
+
+        if !file.path.is_empty() {
+            location(f, file.line, file.col)?;
+            write!(f, " - ")?;
+        }
+        color(level_color, f)?;
+        write!(f, "{}", diag.level())?;
+        normal(f)?;
+        color(8, f)?;
+        match diag.error_code() {
+            Some(code) if code.bytes().all(|b| b.is_ascii_digit()) => write!(f, " TS{code}: ")?,
+            Some(code) => write!(f, " {code}: ")?,
+            None => write!(f, ": ")?,
+        }
+        normal(f)?;
+        writeln!(f, "{}", diag.message())?;
+
+        let labels = diag
+            .code()
+            .iter()
+            .filter_map(|l| l.marker.as_ref()?.message.as_deref());
+        for message in labels {
+            writeln!(f, "  {message}")?;
+        }
+
+        // nested children are chained further in
+        for (depth, child) in descendants(diag.children()) {
+            let message = match &child.kind {
+                ChildKind::Help(h) if h.suggestion.is_some() => continue,
+                ChildKind::Help(h) => &h.message,
+                ChildKind::Note(n) | ChildKind::Warning(n) => &n.0,
+            };
+            writeln!(f, "  {}{message}", Repeat(depth * 2, ' '))?;
+        }
+
+        writeln!(f)?;
+        tsc_code(diag.code(), "", level_color, f)?;
+        writeln!(f)?;
+
+        for help in diag.helps() {
+            let Some(suggestion) = &help.suggestion else {
+                continue;
+            };
+
+            write!(f, "  ")?;
+            let line = suggestion.first().map_or(file.line, |l| l.line_number);
+            let col = suggestion
+                .iter()
+                .find_map(|l| l.marker.as_ref())
+                .map_or(1, |m| m.range.start() + 1);
+            location(f, line, col)?;
+            writeln!(f)?;
+
+            tsc_code(suggestion, "    ", 14, f)?;
+            writeln!(f, "    {}", help.message)?;
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write out `code` in [`Tsc`]'s style, indented by `indent`, with `~`s in
+/// `squiggle` color.
+fn tsc_code(code: &Code, indent: &str, squiggle: u8, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let width = code.line_number_width();
+    let gutter = |f: &mut fmt::Formatter<'_>, number: &dyn fmt::Display| {
+        write!(f, "{indent}\x1b[7m{number: >width$}")?;
+        normal(f)?;
+        write!(f, " ")
+    };
+
+    for (line, markers) in grouped(code) {
+        gutter(f, &line.line_number)?;
+        writeln!(f, "{}", line.code)?;
+
+        if !markers.is_empty() {
+            gutter(f, &"")?;
+            underline(f, &markers, |m| {
+                let len = m.range.end().saturating_sub(*m.range.start()) + 1;
+                (Repeat(len, '~').to_string(), squiggle)
+            })?;
+            writeln!(f)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Python's traceback style:
+///
+/// ```text
+/// Traceback (most recent call last):
+///   File "src/main.py", line 3, in <module>
+///     print(conut)
+///           ^^^^^
+/// NameError: name 'conut' is not defined. Did you mean: 'count'?
+/// ```
+///
+/// The error code is the exception's name, `Error` if there isn't one.
+/// Warnings look like `warnings.warn`'s, as `UserWarning`s. Marker labels,
+/// helps, and notes follow the message, like exception notes; the first
+/// inline suggestion becomes a "Did you mean".
+pub struct Python;
+
+impl Style for Python {
+    fn write(&self, diag: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let file = diag.file();
+        let lines = grouped(diag.code());
+
+        // shown dedented, as Python does
+        let indent = lines
+            .iter()
+            .filter(|(l, _)| !l.code.trim().is_empty())
+            .map(|(l, _)| l.code.chars().take_while(|c| c.is_whitespace()).count())
+            .min()
+            .unwrap_or(0);
+        let code = |f: &mut fmt::Formatter<'_>, prefix: &str| {
+            for (line, markers) in &lines {
+                let text: String = line.code.chars().skip(indent).collect();
+                writeln!(f, "{prefix}{text}")?;
+                if markers.is_empty() {
+                    continue;
+                }
+
+                write!(f, "{prefix}")?;
+                let shifted: Vec<Marker> = markers
+                    .iter()
+                    .map(|m| Marker {
+                        range: m.range.start().saturating_sub(indent)
+                            ..=m.range.end().saturating_sub(indent),
+                        symbol: m.symbol,
+                        color: m.color,
+                        message: None,
+                        color_span: false,
+                    })
+                    .collect();
+                underline(f, &shifted.iter().collect::<Vec<_>>(), |m| {
+                    let len = m.range.end().saturating_sub(*m.range.start()) + 1;
+                    let symbol = if m.symbol == '^' { '^' } else { '~' };
+                    (Repeat(len, symbol).to_string(), 9)
+                })?;
+                writeln!(f)?;
+            }
+
+            Ok::<_, fmt::Error>(())
+        };
+
+        let mut message = diag.message().to_string();
+        if let Some(snippet) = diag.helps().find_map(Help::inline) {
+            message.push_str(&format!(". Did you mean: '{snippet}'?"));
+        }
+
+        match diag.level() {
+            Level::Error => {
+                writeln!(f, "Traceback (most recent call last):")?;
+                write!(f, "  File \"")?;
+                color(5, f)?;
+                write!(f, "{}", file.path)?;
+                normal(f)?;
+                write!(f, "\", line ")?;
+                color(5, f)?;
+                write!(f, "{}", file.line)?;
+                normal(f)?;
+                write!(f, ", in ")?;
+                color(5, f)?;
+                write!(f, "<module>")?;
+                normal(f)?;
+                writeln!(f)?;
+                code(f, "    ")?;
+
+                bold(f)?;
+                color(5, f)?;
+                write!(f, "{}", diag.error_code().unwrap_or("Error"))?;
+                normal(f)?;
+                write!(f, ": ")?;
+                color(5, f)?;
+                write!(f, "{message}")?;
+                normal(f)?;
+                writeln!(f)?;
+            }
+            Level::Warning => {
+                write!(f, "{}:{}: ", file.path, file.line)?;
+                bold(f)?;
+                color(3, f)?;
+                write!(f, "UserWarning")?;
+                normal(f)?;
+                writeln!(f, ": {message}")?;
+                code(f, "  ")?;
+            }
+        }
+
+        let labels = diag
+            .code()
+            .iter()
+            .filter_map(|l| l.marker.as_ref()?.message.as_deref());
+        let children = descendants(diag.children()).filter_map(|(_, c)| match &c.kind {
+            ChildKind::Help(h) if h.inline().is_some() => None,
+            ChildKind::Help(h) => Some(h.message.as_str()),
+            ChildKind::Note(n) | ChildKind::Warning(n) => Some(n.0.as_str()),
+        });
+        for note in labels.chain(children) {
+            writeln!(f, "{note}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A box-drawing style, like `ariadne`'s and `miette`'s:
+///
+/// ```text
+/// error[E0308]: mismatched types
+///    ╭─[src/main.rs:7:17]
+///    │
+///  7 │     let x: u8 = "five";
+///    │                 ───┬──
+///    │                    ╰── expected `u8`, found `&str`
+///    │
+///    │ help: try parsing it: `.parse()?`
+///    ╰────
+/// ```
+///
+/// For terminals that can't show box-drawing characters, it can fall back to
+/// ASCII:
+///
+/// ```text
+/// error[E0308]: mismatched types
+///    ,-[src/main.rs:7:17]
+///    |
+///  7 |     let x: u8 = "five";
+///    |                 ^^^|^^
+///    |                    `-- expected `u8`, found `&str`
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Fancy {
+    /// Whether to draw with ASCII instead of box-drawing characters.
+    pub ascii: bool,
+}
+
+impl Fancy {
+    /// Create a fancy style, drawn with box-drawing characters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to draw with ASCII instead.
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// Create a fancy style, drawn with ASCII unless the locale (`LC_ALL`,
+    /// `LC_CTYPE`, or `LANG`) is UTF-8.
+    pub fn detect() -> Self {
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .into_iter()
+            .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        Self::new().ascii(!locale.contains("utf-8") && !locale.contains("utf8"))
+    }
+
+    fn glyphs(&self) -> &'static Glyphs {
+        if self.ascii {
+            &ASCII
+        } else {
+            &UNICODE
+        }
+    }
+}
+
+/// The characters a [`Fancy`] style draws with.
+struct Glyphs {
+    /// The start of the ` ╭─[file]` header.
+    top: &'static str,
+
+    hbar: char,
+    vbar: char,
+
+    /// Where lines were skipped.
+    gap: char,
+
+    underline: char,
+
+    /// Where a label hangs off an underline.
+    tee: char,
+
+    /// Where a label's arrow turns.
+    corner: char,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    top: "╭─",
+    hbar: '─',
+    vbar: '│',
+    gap: '┆',
+    underline: '─',
+    tee: '┬',
+    corner: '╰',
+};
+
+const ASCII: Glyphs = Glyphs {
+    top: ",-",
+    hbar: '-',
+    vbar: '|',
+    gap: ':',
+    underline: '^',
+    tee: '|',
+    corner: '`',
+};
+
+impl Style for Fancy {
+    fn write(&self, diag: &Diagnostic, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyphs = self.glyphs();
+        let width = std::iter::once(diag.code())
+            .chain(diag.helps().filter_map(|h| h.suggestion.as_ref()))
+            .map(Code::line_number_width)
+            .max()
+            .unwrap_or(1);
+
+        let margin = |f: &mut fmt::Formatter<'_>, c: &dyn fmt::Display| {
+            bold(f)?;
+            color(12, f)?;
+            write!(f, "{: >pad$}{c}", "", pad = width + 2)?;
+            normal(f)
+        };
+        let gutter = |f: &mut fmt::Formatter<'_>| {
+            margin(f, &glyphs.vbar)?;
+            write!(f, " ")
+        };
+
+        bold(f)?;
+        match diag.level() {
+            Level::Error => {
+                color(9, f)?;
+                write!(f, "error")?;
+                if let Some(code) = diag.error_code() {
+                    write!(f, "[{code}]")?;
+                }
+            }
+            Level::Warning => {
+                color(3, f)?;
+                write!(f, "warning")?;
+            }
+        }
+        normal(f)?;
+        bold(f)?;
+        writeln!(f, ": {}", diag.message())?;
+        normal(f)?;
+
+        let file = diag.file();
+        margin(f, &glyphs.top)?;
+        bold(f)?;
+        color(12, f)?;
+        if !file.path.is_empty() {
+            write!(f, "[{}:{}:{}]", file.path, file.line, file.col)?;
+        }
+        normal(f)?;
+        writeln!(f)?;
+
+        if !diag.code().is_empty() {
+            margin(f, &glyphs.vbar)?;
+            writeln!(f)?;
+            self.code(diag.code(), width, f)?;
+        }
+
+        // top-level children are spaced out, except for runs of notes
+        let mut after_note = false;
+        for (depth, child) in descendants(diag.children()) {
+            let is_note = matches!(child.kind, ChildKind::Note(_));
+            if depth == 0 && !(is_note && after_note) {
+                margin(f, &glyphs.vbar)?;
+                writeln!(f)?;
+            }
+            after_note = is_note;
+
+            gutter(f)?;
+            write!(f, "{}", Repeat(depth * 2, ' '))?;
+            let help = match &child.kind {
+                ChildKind::Help(help) => help,
+                ChildKind::Note(note) => {
+                    bold(f)?;
+                    write!(f, "note")?;
+                    normal(f)?;
+                    writeln!(f, ": {}", note.0)?;
+                    continue;
+                }
+                ChildKind::Warning(note) => {
+                    bold(f)?;
+                    color(3, f)?;
+                    write!(f, "warning")?;
+                    normal(f)?;
+                    writeln!(f, ": {}", note.0)?;
+                    continue;
+                }
+            };
+
+            bold(f)?;
+            color(14, f)?;
+            write!(f, "help")?;
+            normal(f)?;
+
+            if let Some(snippet) = help.inline() {
+                writeln!(f, ": {}: `{snippet}`", help.message)?;
+                continue;
+            }
+
+            writeln!(f, ": {}", help.message)?;
+            match &help.suggestion {
+                Some(s) if help.style != SuggestionStyle::Hidden && !s.is_empty() => {
+                    margin(f, &glyphs.vbar)?;
+                    writeln!(f)?;
+                    self.code(s, width, f)?;
+                }
+                _ => {}
+            }
+        }
+
+        margin(f, &glyphs.corner)?;
+        bold(f)?;
+        color(12, f)?;
+        write!(f, "{}", Repeat(4, glyphs.hbar))?;
+        normal(f)?;
+        writeln!(f)
+    }
+}
+
+impl Fancy {
+    /// Write out the lines of `code`, with line numbers `width` wide.
+    fn code(&self, code: &Code, width: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyphs = self.glyphs();
+        let gutter = |f: &mut fmt::Formatter<'_>, c: char| {
+            bold(f)?;
+            color(12, f)?;
+            write!(f, "{: >pad$}{c} ", "", pad = width + 2)?;
+            normal(f)
+        };
+
+        let mut last = None;
+        for (line, markers) in grouped(code) {
+            if last.is_some_and(|l: usize| line.line_number != l + 1) {
+                bold(f)?;
+                color(12, f)?;
+                write!(f, "{: >pad$}{}", "", glyphs.gap, pad = width + 2)?;
+                normal(f)?;
+                writeln!(f)?;
+            }
+            last = Some(line.line_number);
+
+            bold(f)?;
+            color(12, f)?;
+            write!(f, " {: >width$} {} ", line.line_number, glyphs.vbar)?;
+            normal(f)?;
+
+            let colors: Vec<_> = markers
+                .iter()
+                .filter(|m| m.color_span)
+                .map(|m| (edit::bytes(&line.code, &m.range), m.color))
+                .collect();
+            crate::write_colored(&line.code, &colors, f)?;
+            writeln!(f)?;
+
+            if markers.is_empty() {
+                continue;
+            }
+
+            // labels hang off the middle of their underlines
+            let anchor =
+                |m: &Marker| m.range.start() + (m.range.end().saturating_sub(*m.range.start())) / 2;
+
+            gutter(f, glyphs.vbar)?;
+            underline(f, &markers, |m| {
+                let len = m.range.end().saturating_sub(*m.range.start()) + 1;
+                let text = (0..len)
+                    .map(|i| match m.message {
+                        Some(_) if m.range.start() + i == anchor(m) => glyphs.tee,
+                        _ => glyphs.underline,
+                    })
+                    .collect();
+                (text, m.color)
+            })?;
+            writeln!(f)?;
+
+            let mut labeled: Vec<&Marker> = markers
+                .into_iter()
+                .filter(|m| m.message.is_some())
+                .collect();
+            labeled.sort_by_key(|m| *m.range.start());
+            let mut start = 0;
+            labeled.retain(|m| {
+                let keep = *m.range.start() >= start;
+                start = start.max(m.range.end() + 1);
+                keep
+            });
+
+            // the rightmost label first, so arrows never cross
+            for i in (0..labeled.len()).rev() {
+                gutter(f, glyphs.vbar)?;
+                let mut column = 0;
+                for m in &labeled[..i] {
+                    write!(f, "{: >pad$}", "", pad = anchor(m) - column)?;
+                    bold(f)?;
+                    color(m.color, f)?;
+                    write!(f, "{}", glyphs.vbar)?;
+                    normal(f)?;
+                    column = anchor(m) + 1;
+                }
+
+                let m = labeled[i];
+                write!(f, "{: >pad$}", "", pad = anchor(m) - column)?;
+                bold(f)?;
+                color(m.color, f)?;
+                write!(f, "{}{} ", glyphs.corner, Repeat(2, glyphs.hbar))?;
+                write!(f, "{}", m.message.as_deref().unwrap_or_default())?;
+                normal(f)?;
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The lines of `code`, each with the markers of every [`Line`] showing it,
+/// grouped like [`Code::display`] does.
+fn grouped(code: &Code) -> Vec<(&Line, Vec<&Marker>)> {
+    let same = |a: &Line, b: &Line| a.line_number == b.line_number && a.code == b.code;
+    code.chunk_by(same)
+        .map(|lines| {
+            let markers = lines.iter().filter_map(|l| l.marker.as_ref()).collect();
+            (&lines[0], markers)
+        })
+        .collect()
+}
+
+/// Write out an underline for `markers`, each drawn as `draw` says, in its
+/// color. Markers that overlap an earlier one are left out.
+fn underline(
+    f: &mut fmt::Formatter<'_>,
+    markers: &[&Marker],
+    draw: impl Fn(&Marker) -> (String, u8),
+) -> fmt::Result {
+    let mut markers = markers.to_vec();
+    markers.sort_by_key(|m| *m.range.start());
+
+    let mut column = 0;
+    for m in markers {
+        if *m.range.start() < column {
+            continue;
+        }
+
+        let (text, c) = draw(m);
+        write!(f, "{: >pad$}", "", pad = m.range.start() - column)?;
+        bold(f)?;
+        color(c, f)?;
+        write!(f, "{text}")?;
+        normal(f)?;
+        column = m.range.end() + 1;
+    }
+
+    Ok(())
+}
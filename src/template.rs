@@ -0,0 +1,562 @@
+//! A catalogue of common `rustc` errors, as parameterized templates.
+//!
+//! Each [`Template`] has named [`Slot`]s for identifiers, types, and lines of
+//! code; every [`Marker`] range is computed from where those end up.
+//!
+//! ```rust,no_run
+//! use frack::template::{self, Vars};
+//!
+//! let error = template::e0382(&Vars::new().set("name", "eggs")).unwrap();
+//! println!("{error}");
+//! ```
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::{Applicability, Code, Error, File, Help, Line, Marker, SuggestionStyle};
+
+/// A parameterized `rustc` error.
+pub struct Template {
+    /// The error code, e.g. `E0382`.
+    pub code: &'static str,
+
+    /// A short description of the error.
+    pub title: &'static str,
+
+    /// The values that can be filled in.
+    pub slots: &'static [Slot],
+
+    build: fn(&Resolved) -> Result<Error, TemplateError>,
+}
+
+/// A named value in a [`Template`].
+pub struct Slot {
+    pub name: &'static str,
+
+    /// The value to use if none is given.
+    ///
+    /// May refer to other slots, e.g. `let {name} = 5;`.
+    pub default: &'static str,
+}
+
+/// The values to fill a [`Template`]'s [`Slot`]s with.
+#[derive(Default)]
+pub struct Vars(pub Vec<(String, String)>);
+
+impl Vars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the value of a slot, replacing any previous value.
+    pub fn set(mut self, name: impl ToString, value: impl ToString) -> Self {
+        let name = name.to_string();
+        self.0.retain(|(n, _)| *n != name);
+        self.0.push((name, value.to_string()));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Why a [`Template`] couldn't be filled in.
+#[derive(Debug)]
+pub enum TemplateError {
+    /// A variable was given for a slot the template doesn't have.
+    UnknownSlot(String),
+
+    /// The `line` slot wasn't a positive number.
+    InvalidLine(String),
+
+    /// A line of code doesn't contain what it's supposed to point at.
+    NotFound { slot: &'static str, needle: String },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSlot(name) => write!(f, "no slot named `{name}`"),
+            Self::InvalidLine(line) => write!(f, "invalid line number `{line}`"),
+            Self::NotFound { slot, needle } => {
+                write!(f, "slot `{slot}` must contain `{needle}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl Template {
+    /// Fill in the template, using defaults for any missing slots.
+    pub fn build(&self, vars: &Vars) -> Result<Error, TemplateError> {
+        if let Some((name, _)) = vars
+            .0
+            .iter()
+            .find(|(n, _)| n != "path" && n != "line" && !self.slots.iter().any(|s| s.name == n))
+        {
+            return Err(TemplateError::UnknownSlot(name.clone()));
+        }
+
+        let raw: Vec<_> = self
+            .slots
+            .iter()
+            .map(|s| (s.name, vars.get(s.name).unwrap_or(s.default)))
+            .collect();
+
+        let values = raw
+            .iter()
+            .map(|&(name, value)| {
+                let mut value = value.to_string();
+                for (other, v) in &raw {
+                    if *other != name {
+                        value = value.replace(&format!("{{{other}}}"), v);
+                    }
+                }
+                (name, value)
+            })
+            .collect();
+
+        let line = vars.get("line").unwrap_or("2");
+        let resolved = Resolved {
+            values,
+            path: vars.get("path").unwrap_or("src/main.rs").to_string(),
+            line: line
+                .parse()
+                .ok()
+                .filter(|&l| l != 0)
+                .ok_or_else(|| TemplateError::InvalidLine(line.to_string()))?,
+        };
+
+        (self.build)(&resolved)
+    }
+}
+
+/// Every bundled template.
+///
+/// Besides their own slots, all templates accept `path` and `line` (the number
+/// of their first line of code).
+pub static TEMPLATES: &[Template] = &[
+    Template {
+        code: "E0308",
+        title: "mismatched types",
+        slots: &[
+            Slot {
+                name: "expected",
+                default: "i32",
+            },
+            Slot {
+                name: "found",
+                default: "&str",
+            },
+            Slot {
+                name: "expr",
+                default: "\"hello\"",
+            },
+            Slot {
+                name: "code",
+                default: "    let x: {expected} = {expr};",
+            },
+        ],
+        build: build_e0308,
+    },
+    Template {
+        code: "E0382",
+        title: "borrow of moved value",
+        slots: &[
+            Slot {
+                name: "name",
+                default: "v",
+            },
+            Slot {
+                name: "ty",
+                default: "Vec<i32>",
+            },
+            Slot {
+                name: "decl",
+                default: "    let {name} = vec![1, 2, 3];",
+            },
+            Slot {
+                name: "moved",
+                default: "    let w = {name};",
+            },
+            Slot {
+                name: "used",
+                default: "    println!(\"{:?}\", {name});",
+            },
+        ],
+        build: build_e0382,
+    },
+    Template {
+        code: "E0425",
+        title: "cannot find value in this scope",
+        slots: &[
+            Slot {
+                name: "name",
+                default: "y",
+            },
+            Slot {
+                name: "similar",
+                default: "",
+            },
+            Slot {
+                name: "code",
+                default: "    println!(\"{}\", {name});",
+            },
+        ],
+        build: build_e0425,
+    },
+    Template {
+        code: "E0502",
+        title: "cannot borrow as mutable because it is also borrowed as immutable",
+        slots: &[
+            Slot {
+                name: "name",
+                default: "v",
+            },
+            Slot {
+                name: "reference",
+                default: "first",
+            },
+            Slot {
+                name: "borrow",
+                default: "    let {reference} = &{name}[0];",
+            },
+            Slot {
+                name: "mutate",
+                default: "    {name}.push(4);",
+            },
+            Slot {
+                name: "used",
+                default: "    println!(\"{}\", {reference});",
+            },
+        ],
+        build: build_e0502,
+    },
+    Template {
+        code: "E0599",
+        title: "no method found",
+        slots: &[
+            Slot {
+                name: "method",
+                default: "foo",
+            },
+            Slot {
+                name: "ty",
+                default: "Bar",
+            },
+            Slot {
+                name: "receiver",
+                default: "bar",
+            },
+            Slot {
+                name: "code",
+                default: "    {receiver}.{method}();",
+            },
+        ],
+        build: build_e0599,
+    },
+];
+
+/// Find a bundled template by its error code.
+pub fn find(code: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.code.eq_ignore_ascii_case(code))
+}
+
+/// `error[E0308]: mismatched types`.
+///
+/// Slots: `expected`, `found`, `expr`, `code`.
+pub fn e0308(vars: &Vars) -> Result<Error, TemplateError> {
+    TEMPLATES[0].build(vars)
+}
+
+/// `error[E0382]: borrow of moved value`.
+///
+/// Slots: `name`, `ty`, `decl`, `moved`, `used`.
+pub fn e0382(vars: &Vars) -> Result<Error, TemplateError> {
+    TEMPLATES[1].build(vars)
+}
+
+/// `error[E0425]: cannot find value in this scope`.
+///
+/// Slots: `name`, `similar` (a similarly-named local, or empty), `code`.
+pub fn e0425(vars: &Vars) -> Result<Error, TemplateError> {
+    TEMPLATES[2].build(vars)
+}
+
+/// `error[E0502]: cannot borrow as mutable because it is also borrowed as immutable`.
+///
+/// Slots: `name`, `reference`, `borrow`, `mutate`, `used`.
+pub fn e0502(vars: &Vars) -> Result<Error, TemplateError> {
+    TEMPLATES[3].build(vars)
+}
+
+/// `error[E0599]: no method found`.
+///
+/// Slots: `method`, `ty`, `receiver`, `code`.
+pub fn e0599(vars: &Vars) -> Result<Error, TemplateError> {
+    TEMPLATES[4].build(vars)
+}
+
+/// The slot values of a template, with defaults and references filled in.
+struct Resolved {
+    values: Vec<(&'static str, String)>,
+    path: String,
+    line: usize,
+}
+
+impl Resolved {
+    fn get(&self, name: &str) -> &str {
+        self.values
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map_or("", |(_, v)| v.as_str())
+    }
+
+    /// Find the columns of `needle` in the slot `slot`, as a whole word if it's
+    /// an identifier.
+    ///
+    /// If `last`, finds the last occurrence instead of the first.
+    fn find(
+        &self,
+        slot: &'static str,
+        needle: &str,
+        last: bool,
+    ) -> Result<RangeInclusive<usize>, TemplateError> {
+        let code = self.get(slot);
+        let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+        let whole = needle.chars().all(is_ident);
+
+        let mut found = code.match_indices(needle).filter(|&(i, _)| {
+            !whole
+                || (!code[..i].ends_with(is_ident)
+                    && !code[i + needle.len()..].starts_with(is_ident))
+        });
+
+        let found = if last { found.last() } else { found.next() };
+        match found {
+            Some((i, _)) if !needle.is_empty() => {
+                let start = code[..i].chars().count();
+                Ok(start..=start + needle.chars().count() - 1)
+            }
+            _ => Err(TemplateError::NotFound {
+                slot,
+                needle: needle.to_string(),
+            }),
+        }
+    }
+
+    /// The slot `slot`, trimmed of whitespace and a trailing semicolon.
+    fn statement(&self, slot: &'static str) -> Result<RangeInclusive<usize>, TemplateError> {
+        let code = self.get(slot);
+        let statement = code.trim().trim_end_matches(';').trim_end();
+        self.find(slot, statement, false)
+    }
+
+    fn line(&self, offset: usize, slot: &str, marker: Marker) -> Line {
+        Line {
+            code: self.get(slot).to_string(),
+            line_number: self.line + offset,
+            marker: Some(marker),
+        }
+    }
+
+    fn error(
+        &self,
+        code: &str,
+        message: String,
+        offset: usize,
+        col: usize,
+        lines: Vec<Line>,
+    ) -> Error {
+        Error {
+            error_code: code.to_string(),
+            message,
+            file: File {
+                path: self.path.clone(),
+                line: self.line + offset,
+                col: col + 1,
+            },
+            code: Code(lines),
+            children: Vec::new(),
+        }
+    }
+}
+
+fn primary(range: RangeInclusive<usize>, message: String) -> Marker {
+    Marker {
+        range,
+        symbol: '^',
+        color: 9,
+        message: Some(message),
+        color_span: false,
+    }
+}
+
+fn secondary(range: RangeInclusive<usize>, message: String) -> Marker {
+    Marker {
+        range,
+        symbol: '-',
+        color: 12,
+        message: Some(message),
+        color_span: false,
+    }
+}
+
+fn build_e0308(r: &Resolved) -> Result<Error, TemplateError> {
+    let expr = r.find("code", r.get("expr"), true)?;
+    let col = *expr.start();
+    let marker = primary(
+        expr,
+        format!(
+            "expected `{}`, found `{}`",
+            r.get("expected"),
+            r.get("found")
+        ),
+    );
+
+    Ok(r.error(
+        "E0308",
+        "mismatched types".to_string(),
+        0,
+        col,
+        vec![r.line(0, "code", marker)],
+    ))
+}
+
+fn build_e0382(r: &Resolved) -> Result<Error, TemplateError> {
+    let name = r.get("name");
+    let decl = r.find("decl", name, false)?;
+    let moved = r.find("moved", name, true)?;
+    let used = r.find("used", name, true)?;
+    let col = *used.start();
+
+    let mut fixed = r.get("moved").to_string();
+    fixed.insert_str(crate::edit::bytes(&fixed, &moved).end, ".clone()");
+    let clone = moved.end() + 1..=moved.end() + 8;
+
+    let mut error = r.error(
+        "E0382",
+        format!("borrow of moved value: `{name}`"),
+        2,
+        col,
+        vec![
+            r.line(
+                0,
+                "decl",
+                secondary(
+                    decl,
+                    format!(
+                        "move occurs because `{name}` has type `{}`, which does not implement the `Copy` trait",
+                        r.get("ty")
+                    ),
+                ),
+            ),
+            r.line(1, "moved", secondary(moved, "value moved here".to_string())),
+            r.line(
+                2,
+                "used",
+                primary(used, "value borrowed here after move".to_string()),
+            ),
+        ],
+    );
+
+    error.children.push(
+        Help {
+            message: "consider cloning the value if the performance cost is acceptable".to_string(),
+            suggestion: Some(Code::single(
+                fixed,
+                r.line + 1,
+                Some(Marker {
+                    range: clone,
+                    symbol: '+',
+                    color: 10,
+                    message: None,
+                    color_span: true,
+                }),
+            )),
+            // `rustc` always shows this one in full
+            style: SuggestionStyle::Verbose,
+            applicability: Applicability::Unspecified,
+        }
+        .into(),
+    );
+
+    Ok(error)
+}
+
+fn build_e0425(r: &Resolved) -> Result<Error, TemplateError> {
+    let name = r.get("name");
+    let found = r.find("code", name, true)?;
+    let col = *found.start();
+
+    let similar = r.get("similar");
+    let message = if similar.is_empty() {
+        "not found in this scope".to_string()
+    } else {
+        format!("help: a local variable with a similar name exists: `{similar}`")
+    };
+
+    Ok(r.error(
+        "E0425",
+        format!("cannot find value `{name}` in this scope"),
+        0,
+        col,
+        vec![r.line(0, "code", primary(found, message))],
+    ))
+}
+
+fn build_e0502(r: &Resolved) -> Result<Error, TemplateError> {
+    let name = r.get("name");
+    let borrow = r.find("borrow", &format!("&{name}"), false)?;
+    let mutate = r.statement("mutate")?;
+    let used = r.find("used", r.get("reference"), true)?;
+    let col = *mutate.start();
+
+    Ok(r.error(
+        "E0502",
+        format!("cannot borrow `{name}` as mutable because it is also borrowed as immutable"),
+        1,
+        col,
+        vec![
+            r.line(
+                0,
+                "borrow",
+                secondary(borrow, "immutable borrow occurs here".to_string()),
+            ),
+            r.line(
+                1,
+                "mutate",
+                primary(mutate, "mutable borrow occurs here".to_string()),
+            ),
+            r.line(
+                2,
+                "used",
+                secondary(used, "immutable borrow later used here".to_string()),
+            ),
+        ],
+    ))
+}
+
+fn build_e0599(r: &Resolved) -> Result<Error, TemplateError> {
+    let method = r.get("method");
+    let ty = r.get("ty");
+    let found = r.find("code", method, true)?;
+    let col = *found.start();
+
+    Ok(r.error(
+        "E0599",
+        format!("no method named `{method}` found for struct `{ty}` in the current scope"),
+        0,
+        col,
+        vec![r.line(
+            0,
+            "code",
+            primary(found, format!("method not found in `{ty}`")),
+        )],
+    ))
+}
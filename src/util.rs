@@ -3,6 +3,13 @@
 /// Auto-fills certain details for you: for example, sets the proper symbols/colors for
 /// [`Marker`](crate::Marker)s.
 ///
+/// Spans can be column ranges like `21..=28`, or anything else that
+/// [locates](crate::locate::Locate) them, like ``"`*string`"``.
+///
+/// # Panics
+///
+/// If a span doesn't locate anything, e.g. text that isn't in the code.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -29,7 +36,10 @@
 /// # let suggestion = "suggestion";
 /// # let diff = 0..=9;
 /// # let tip = "tip";
+/// # let fix_message = "fix message";
+/// # let fixed = "fixed code";
 /// # let note = "note";
+/// # let warning = "warning";
 /// let error = frack::error! {
 ///     error_code, message; // error[<error_code>]: <message>
 ///     path, line, col;     //  --> <path>:<line>:<col>
@@ -37,7 +47,7 @@
 ///                          //            <span> <span_message>
 ///     span /* optional: */ => span_message;
 ///
-///     // any number of:
+///     // any number of, in any order:
 ///                          // help: <help_message>
 ///                          //  <line> | <suggestion>
 ///                          //            <diff> <tip>
@@ -47,9 +57,23 @@
 ///         /* optional: */ ; tip
 ///     ];
 ///
-///     // any number of:
+///                          // help: <fix_message>
+///                          //  <line> | <fixed>
+///                          //            <what changed in <fixed>, e.g. ~~~>
+///     fix fix_message => fixed;
+///
 ///                          // note: <note>
 ///     note note;
+///
+///                          // warning: <warning>
+///     warning warning;
+///
+///     // any of which can be followed by nested children, e.g.:
+///                          // note: <note>
+///                          //   help: <help_message>
+///     note note => {
+///         help help_message;
+///     };
 /// };
 /// ```
 #[macro_export]
@@ -59,13 +83,9 @@ macro_rules! error {
         $path:expr, $line:expr, $col: expr;
         $code:expr;
         $span:expr $(=> $span_message:expr)?;
-        $(
-            help $help:expr
-            $( => [$suggestion:expr $(; $diff:expr $(; $tip:expr)? )?] )?
-            ;
-        )*
-        $( note $note:expr; )*
-    ) => {
+        $($children:tt)*
+    ) => {{
+        let code = ::std::string::ToString::to_string(&$code);
         $crate::Error {
             error_code: $error_code.into(),
             message: $message.into(),
@@ -75,34 +95,18 @@ macro_rules! error {
                 col: $col,
             },
             code: $crate::Code::single(
-                $code, $line,
+                &code, $line,
                 Some($crate::Marker {
-                    range: $span,
+                    range: $crate::locate::expect(&$span, &code),
                     symbol: '^',
                     color: 9,
                     message: $crate::if_else!([$( Some($span_message.into()) )?][None]),
                     color_span: false,
                 }),
             ),
-            helps: ::std::vec![$(
-                $crate::Help {
-                    message: $help.into(),
-                    suggestion: $crate::if_else!([$(Some($crate::Code::single(
-                        $suggestion,
-                        $line,
-                        $crate::if_else!([$(Some($crate::Marker {
-                            range: $diff,
-                            symbol: '~',
-                            color: 10,
-                            message: $crate::if_else!([$( Some($tip.into()) )?][None]),
-                            color_span: true,
-                        }))?][None]),
-                    )))?][None]),
-                },
-           )*],
-            notes: ::std::vec![$( $crate::Note($note.into()), )*],
+            children: $crate::children!(code, $line; [] $($children)*),
         }
-    };
+    }};
 }
 
 /// Create a new [Warning](crate::Warning).
@@ -110,6 +114,13 @@ macro_rules! error {
 /// Auto-fills certain details for you: for example, sets the proper symbols/colors for
 /// [`Marker`](crate::Marker)s.
 ///
+/// Spans can be column ranges like `21..=28`, or anything else that
+/// [locates](crate::locate::Locate) them, like ``"`*string`"``.
+///
+/// # Panics
+///
+/// If a span doesn't locate anything, e.g. text that isn't in the code.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -139,7 +150,10 @@ macro_rules! error {
 /// # let suggestion = "suggestion";
 /// # let diff = 0..=9;
 /// # let tip = "tip";
+/// # let fix_message = "fix message";
+/// # let fixed = "fixed code";
 /// # let note = "note";
+/// # let warning = "warning";
 /// let error = frack::warning! {
 ///     message;             // warning: <message>
 ///     path, line, col;     //  --> <path>:<line>:<col>
@@ -147,7 +161,7 @@ macro_rules! error {
 ///                          //            <span> <span_message>
 ///     span /* optional: */ => span_message;
 ///
-///     // any number of:
+///     // any number of, in any order:
 ///                          // help: <help_message>
 ///                          //  <line> | <suggestion>
 ///                          //            <diff> <tip>
@@ -157,9 +171,23 @@ macro_rules! error {
 ///         /* optional: */ ; tip
 ///     ];
 ///
-///     // any number of:
+///                          // help: <fix_message>
+///                          //  <line> | <fixed>
+///                          //            <what changed in <fixed>, e.g. ~~~>
+///     fix fix_message => fixed;
+///
 ///                          // note: <note>
 ///     note note;
+///
+///                          // warning: <warning>
+///     warning warning;
+///
+///     // any of which can be followed by nested children, e.g.:
+///                          // note: <note>
+///                          //   help: <help_message>
+///     note note => {
+///         help help_message;
+///     };
 /// };
 /// ```
 #[macro_export]
@@ -169,13 +197,9 @@ macro_rules! warning {
         $path:expr, $line:expr, $col: expr;
         $code:expr;
         $span:expr $(=> $span_message:expr)?;
-        $(
-            help $help:expr
-            $( => [$suggestion:expr $(; $diff:expr $(; $tip:expr)? )?] )?
-            ;
-        )*
-        $( note $note:expr; )*
-    ) => {
+        $($children:tt)*
+    ) => {{
+        let code = ::std::string::ToString::to_string(&$code);
         $crate::Warning {
             message: $message.into(),
             file: $crate::File {
@@ -184,33 +208,164 @@ macro_rules! warning {
                 col: $col,
             },
             code: $crate::Code::single(
-                $code, $line,
+                &code, $line,
                 Some($crate::Marker {
-                    range: $span,
+                    range: $crate::locate::expect(&$span, &code),
                     symbol: '^',
                     color: 9,
                     message: $crate::if_else!([$( Some($span_message.into()) )?][None]),
                     color_span: false,
                 }),
             ),
-            helps: ::std::vec![$(
-                $crate::Help {
-                    message: $help.into(),
-                    suggestion: $crate::if_else!([$(Some($crate::Code::single(
-                        $suggestion,
+            children: $crate::children!(code, $line; [] $($children)*),
+        }
+    }};
+}
+
+/// Declare a [Registry](crate::codes::Registry) of error codes.
+///
+/// Every code must be an uppercase ASCII letter followed by four digits, and no
+/// code may be declared twice; both are checked at compile time.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// frack::codes! {
+///     pub static CODES = "mytool";
+///
+///     E0001 => "A value was used before it was initialized.\n\nErroneous code example: ...",
+///     E0002 => "A value was moved out of a shared reference.",
+/// }
+///
+/// if let Some(explanation) = CODES.explain("E0001") {
+///     println!("{}", frack::markdown::Markdown(explanation));
+/// }
+///
+/// let error = frack::error! {
+///     "E0001", "used before it was initialized";
+///     "src/main.rs", 3, 5;
+///     "    x + 1";
+///     4..=4;
+/// };
+///
+/// // ends with a footer pointing at `mytool --explain E0001`
+/// println!("{}", CODES.explained(&error));
+/// ```
+#[macro_export]
+macro_rules! codes {
+    (
+        $(#[$attr:meta])*
+        $vis:vis static $name:ident = $tool:expr;
+        $( $code:ident => $explanation:expr ),* $(,)?
+    ) => {
+        $(#[$attr])*
+        $vis static $name: $crate::codes::Registry = $crate::codes::Registry {
+            tool: $tool,
+            entries: &[$(
+                $crate::codes::Entry {
+                    code: ::std::stringify!($code),
+                    explanation: $explanation,
+                },
+            )*],
+        };
+
+        const _: () = $crate::codes::validate(&[$( ::std::stringify!($code) ),*]);
+    };
+}
+
+/// What the [`error!`](crate::error) and [`warning!`](crate::warning) macros
+/// use to build their children, one at a time.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! children {
+    ($code:ident, $line:expr; [$($acc:expr,)*]) => {
+        ::std::vec![$($acc,)*]
+    };
+
+    (
+        $code:ident, $line:expr; [$($acc:expr,)*]
+        help $help:expr
+        $( => [$suggestion:expr $(; $diff:expr $(; $tip:expr)? )?] )?
+        $( => { $($inner:tt)* } )?
+        ;
+        $($rest:tt)*
+    ) => {
+        $crate::children!($code, $line; [$($acc,)* $crate::Child {
+            kind: $crate::ChildKind::Help($crate::Help {
+                message: $help.into(),
+                suggestion: $crate::if_else!([$({
+                    let suggestion = ::std::string::ToString::to_string(&$suggestion);
+                    Some($crate::Code::single(
+                        &suggestion,
                         $line,
                         $crate::if_else!([$(Some($crate::Marker {
-                            range: $diff,
+                            range: $crate::locate::expect(&$diff, &suggestion),
                             symbol: '~',
                             color: 10,
                             message: $crate::if_else!([$( Some($tip.into()) )?][None]),
                             color_span: true,
                         }))?][None]),
-                    )))?][None]),
-                },
-           )*],
-            notes: ::std::vec![$( $crate::Note($note.into()), )*],
-        }
+                    ))
+                })?][None]),
+                style: $crate::SuggestionStyle::Verbose,
+                applicability: $crate::Applicability::Unspecified,
+            }),
+            children: $crate::if_else!(
+                [$( $crate::children!($code, $line; [] $($inner)*) )?]
+                [::std::vec::Vec::new()]
+            ),
+        },] $($rest)*)
+    };
+
+    (
+        $code:ident, $line:expr; [$($acc:expr,)*]
+        fix $fix:expr => $fixed:expr $( => { $($inner:tt)* } )?;
+        $($rest:tt)*
+    ) => {
+        $crate::children!($code, $line; [$($acc,)* $crate::Child {
+            kind: $crate::ChildKind::Help($crate::Help {
+                message: $fix.into(),
+                suggestion: ::std::option::Option::Some($crate::Code::suggestion(
+                    &$code,
+                    &::std::string::ToString::to_string(&$fixed),
+                    $line,
+                )),
+                style: $crate::SuggestionStyle::Verbose,
+                applicability: $crate::Applicability::Unspecified,
+            }),
+            children: $crate::if_else!(
+                [$( $crate::children!($code, $line; [] $($inner)*) )?]
+                [::std::vec::Vec::new()]
+            ),
+        },] $($rest)*)
+    };
+
+    (
+        $code:ident, $line:expr; [$($acc:expr,)*]
+        note $note:expr $( => { $($inner:tt)* } )?;
+        $($rest:tt)*
+    ) => {
+        $crate::children!($code, $line; [$($acc,)* $crate::Child {
+            kind: $crate::ChildKind::Note($crate::Note($note.into())),
+            children: $crate::if_else!(
+                [$( $crate::children!($code, $line; [] $($inner)*) )?]
+                [::std::vec::Vec::new()]
+            ),
+        },] $($rest)*)
+    };
+
+    (
+        $code:ident, $line:expr; [$($acc:expr,)*]
+        warning $warning:expr $( => { $($inner:tt)* } )?;
+        $($rest:tt)*
+    ) => {
+        $crate::children!($code, $line; [$($acc,)* $crate::Child {
+            kind: $crate::ChildKind::Warning($crate::Note($warning.into())),
+            children: $crate::if_else!(
+                [$( $crate::children!($code, $line; [] $($inner)*) )?]
+                [::std::vec::Vec::new()]
+            ),
+        },] $($rest)*)
     };
 }
 
@@ -0,0 +1,143 @@
+//! JUnit XML and Checkstyle XML reports, for CI systems that only understand
+//! those.
+//!
+//! Both group diagnostics by [`File::path`](crate::File::path).
+
+use std::fmt::Write;
+
+use crate::{edit, Diagnostic, Plain};
+
+/// Serialize diagnostics as a Checkstyle XML report.
+pub fn checkstyle(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<checkstyle version=\"4.3\">\n");
+
+    for (path, diags) in by_file(diagnostics) {
+        let _ = writeln!(out, "  <file name=\"{}\">", escape(path));
+        for diag in diags {
+            let (line, col) = location(diag);
+            let source = diag
+                .error_code()
+                .map_or_else(|| diag.level().to_string(), str::to_string);
+
+            let _ = writeln!(
+                out,
+                "    <error line=\"{line}\" column=\"{col}\" severity=\"{}\" message=\"{}\" source=\"{}\"/>",
+                diag.level(),
+                escape(&diag.labeled_message()),
+                escape(&source),
+            );
+        }
+        out.push_str("  </file>\n");
+    }
+
+    out.push_str("</checkstyle>\n");
+    out
+}
+
+/// Serialize diagnostics as a JUnit XML report, with one test suite per file.
+///
+/// Every diagnostic is a failed test case, with the diagnostic rendered as
+/// plain text in the failure body.
+pub fn junit(name: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"{}\" tests=\"{n}\" failures=\"{n}\">",
+        escape(name),
+        n = diagnostics.len(),
+    );
+
+    for (path, diags) in by_file(diagnostics) {
+        let suite = if path.is_empty() { name } else { path };
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{n}\" failures=\"{n}\">",
+            escape(suite),
+            n = diags.len(),
+        );
+
+        for diag in diags {
+            let (line, _) = location(diag);
+            let case = match diag.error_code() {
+                Some(code) => format!("{code}: {}", diag.message()),
+                None => format!("{}: {}", diag.level(), diag.message()),
+            };
+
+            let _ = write!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape(&case),
+                escape(suite),
+            );
+            if !path.is_empty() {
+                let _ = write!(out, " file=\"{}\" line=\"{line}\"", escape(path));
+            }
+            out.push_str(">\n");
+
+            let _ = writeln!(
+                out,
+                "      <failure message=\"{}\" type=\"{}\">{}</failure>",
+                escape(&diag.labeled_message()),
+                diag.level(),
+                escape_text(&Plain(diag).to_string()),
+            );
+            out.push_str("    </testcase>\n");
+        }
+
+        out.push_str("  </testsuite>\n");
+    }
+
+    out.push_str("</testsuites>\n");
+    out
+}
+
+/// Group diagnostics by path, in order of first appearance.
+fn by_file(diagnostics: &[Diagnostic]) -> Vec<(&str, Vec<&Diagnostic>)> {
+    let mut files: Vec<(&str, Vec<&Diagnostic>)> = Vec::new();
+    for diag in diagnostics {
+        let path = diag.file().path.as_str();
+        match files.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, diags)) => diags.push(diag),
+            None => files.push((path, vec![diag])),
+        }
+    }
+    files
+}
+
+/// The line and 1-based column of the primary marker, or of the file if
+/// there is none.
+fn location(diag: &Diagnostic) -> (usize, usize) {
+    let marked = diag.primary().and_then(|line| {
+        let range = edit::marked(line, &line.marker.as_ref()?.range);
+        Some((line.line_number, edit::column(&line.code, range.start) + 1))
+    });
+
+    marked.unwrap_or((diag.file().line, diag.file().col))
+}
+
+/// Escape text for XML attributes, dropping characters XML 1.0 doesn't allow.
+fn escape(s: &str) -> String {
+    escape_text(s).replace('\n', "&#10;")
+}
+
+/// Escape text for XML content, dropping characters XML 1.0 doesn't allow.
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\n' => out.push('\n'),
+            '\t' | '\r' => {
+                let _ = write!(out, "&#{};", ch as u32);
+            }
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}